@@ -6,6 +6,13 @@
 
 import React, { useState, useEffect } from 'react';
 import { derivationWallet } from '../services/derivationWallet';
+import { ApiService } from '../services/api';
+import { bytesToHex, deserializeUserKey } from '../services/cryptography';
+import type { AppResourceView } from '../types/api';
+
+// Resources are sent through the generic /apps endpoints; the counter is the only
+// registered app that supports transfers.
+const TRANSFER_APP = 'counter';
 
 interface TransferPageProps {
   userKey: string;
@@ -14,18 +21,17 @@ interface TransferPageProps {
 
 interface TransferForm {
   recipientUserKey: string;
-  resourceType: string;
-  amount: number;
+  commitment: string; // resource to send
   message: string;
 }
 
 export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType }) => {
   const [transferForm, setTransferForm] = useState<TransferForm>({
     recipientUserKey: '',
-    resourceType: 'ANOMA',
-    amount: 0,
+    commitment: '',
     message: ''
   });
+  const [resources, setResources] = useState<AppResourceView[]>([]);
   const [isTransferring, setIsTransferring] = useState(false);
   const [transferResult, setTransferResult] = useState<any>(null);
   const [walletAddress, setWalletAddress] = useState<string>('');
@@ -38,12 +44,18 @@ export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType
     try {
       const address = await derivationWallet.getAddress();
       setWalletAddress(address);
+      await loadResources(address);
     } catch (error) {
       console.error('Failed to load wallet info:', error);
     }
   };
 
-  const handleInputChange = (field: keyof TransferForm, value: string | number) => {
+  const loadResources = async (address: string) => {
+    const all = await ApiService.listAppResources(TRANSFER_APP, address);
+    setResources(all.filter(resource => !resource.consumed_in));
+  };
+
+  const handleInputChange = (field: keyof TransferForm, value: string) => {
     setTransferForm(prev => ({
       ...prev,
       [field]: value
@@ -59,33 +71,50 @@ export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType
       if (!transferForm.recipientUserKey.trim()) {
         throw new Error('Recipient User Key is required');
       }
-      if (transferForm.amount <= 0) {
-        throw new Error('Amount must be greater than 0');
+      if (!transferForm.commitment) {
+        throw new Error('Select a resource to send');
       }
+      const adapter = derivationWallet.getCurrentAdapter();
+      if (!adapter) {
+        throw new Error('Wallet is not connected');
+      }
+
+      // Step 1: Sign the resource and the recipient's nullifier key commitment
+      const recipientUserKey = transferForm.recipientUserKey.trim();
+      const recipientCnk = `0x${bytesToHex(deserializeUserKey(recipientUserKey).cnk)}`;
+      const timestamp = new Date().toISOString();
+      const signedMessage = ApiService.generateAppMessage('transfer', walletAddress, timestamp, {
+        commitment: transferForm.commitment,
+        recipientCnk,
+      });
+      const signature = await adapter.signMessage(signedMessage);
 
-      // TODO: Implement actual transfer logic
-      // For now, simulate the transfer process
-      await new Promise(resolve => setTimeout(resolve, 2000));
+      // Step 2: Prove and submit the transfer
+      const response = await ApiService.transferAppResource(TRANSFER_APP, {
+        user_account: walletAddress,
+        signature,
+        signed_message: signedMessage,
+        timestamp,
+        commitment: transferForm.commitment,
+        recipient_user_key: recipientUserKey,
+      });
 
-      const mockResult = {
+      setTransferResult({
         success: true,
-        transactionId: `tx_${Date.now()}`,
-        recipient: transferForm.recipientUserKey.slice(0, 20) + '...',
-        amount: transferForm.amount,
-        resourceType: transferForm.resourceType,
+        transactionId: response.transaction_hash,
+        recipient: recipientUserKey.slice(0, 20) + '...',
+        sent: response.sent,
         message: transferForm.message,
-        timestamp: new Date().toISOString()
-      };
+        timestamp
+      });
 
-      setTransferResult(mockResult);
-      
       // Reset form
       setTransferForm({
         recipientUserKey: '',
-        resourceType: 'ANOMA',
-        amount: 0,
+        commitment: '',
         message: ''
       });
+      await loadResources(walletAddress);
 
     } catch (error) {
       setTransferResult({
@@ -167,33 +196,22 @@ export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType
             />
           </div>
 
-          <div className="form-row">
-            <div className="form-group">
-              <label htmlFor="resourceType">Resource Type</label>
-              <select
-                id="resourceType"
-                value={transferForm.resourceType}
-                onChange={(e) => handleInputChange('resourceType', e.target.value)}
-              >
-                <option value="ANOMA">ANOMA</option>
-                <option value="ETH">ETH</option>
-                <option value="USDC">USDC</option>
-                <option value="DAI">DAI</option>
-              </select>
-            </div>
-
-            <div className="form-group">
-              <label htmlFor="amount">Amount *</label>
-              <input
-                type="number"
-                id="amount"
-                value={transferForm.amount}
-                onChange={(e) => handleInputChange('amount', parseFloat(e.target.value) || 0)}
-                min="0"
-                step="0.01"
-                placeholder="0.00"
-              />
-            </div>
+          <div className="form-group">
+            <label htmlFor="commitment">Resource *</label>
+            <select
+              id="commitment"
+              value={transferForm.commitment}
+              onChange={(e) => handleInputChange('commitment', e.target.value)}
+            >
+              <option value="">
+                {resources.length ? 'Select a resource...' : 'No spendable resources'}
+              </option>
+              {resources.map(resource => (
+                <option key={resource.commitment} value={resource.commitment}>
+                  {resource.app} {JSON.stringify(resource.data)} ({resource.commitment.slice(0, 10)}...)
+                </option>
+              ))}
+            </select>
           </div>
 
           <div className="form-group">
@@ -211,7 +229,7 @@ export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType
           <div className="transfer-actions">
             <button
               onClick={handleTransfer}
-              disabled={isTransferring || !transferForm.recipientUserKey.trim() || transferForm.amount <= 0}
+              disabled={isTransferring || !transferForm.recipientUserKey.trim() || !transferForm.commitment}
               className="primary-button transfer-button"
             >
               {isTransferring ? '🔄 Processing Transfer...' : '💸 Send Resources'}
@@ -235,10 +253,15 @@ export const TransferPage: React.FC<TransferPageProps> = ({ userKey, walletType
                     <label>Recipient:</label>
                     <span>{transferResult.recipient}</span>
                   </div>
-                  <div className="result-item">
-                    <label>Amount:</label>
-                    <span>{transferResult.amount} {transferResult.resourceType}</span>
-                  </div>
+                  {transferResult.sent && (
+                    <div className="result-item">
+                      <label>Sent Resource:</label>
+                      <span>
+                        {JSON.stringify(transferResult.sent.view.data)}{' '}
+                        <code>{transferResult.sent.view.commitment}</code>
+                      </span>
+                    </div>
+                  )}
                   {transferResult.message && (
                     <div className="result-item">
                       <label>Message:</label>
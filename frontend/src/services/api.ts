@@ -5,7 +5,14 @@ import type {
   CounterResult, 
   CounterAction,
   EmitTransactionRequest,
-  EmitTransactionResponse
+  EmitTransactionResponse,
+  EmitRealTransactionResponse,
+  TransactionShape,
+  AppOperation,
+  AppOperationRequest,
+  AppOperationResponse,
+  AppResourceView,
+  PublishedPayload
 } from '../types/api';
 
 const API_BASE_URL = '/api';
@@ -19,14 +26,24 @@ const apiClient = axios.create({
 
 export class ApiService {
   /**
-   * Generate message for user to sign
+   * Generate message for user to sign. The amount of an add and the id of a shared
+   * counter are signed as extra lines.
    */
-  static generateSigningMessage(action: CounterAction, userAccount: string, timestamp: string): string {
+  static generateSigningMessage(
+    action: CounterAction,
+    userAccount: string,
+    timestamp: string,
+    options: { amount?: number; counterId?: string } = {}
+  ): string {
+    const extraLines = [
+      options.amount !== undefined ? `Amount: ${options.amount}\n` : '',
+      options.counterId ? `Counter: ${options.counterId}\n` : '',
+    ].join('');
     return `Anoma Counter Authorization
 
 Action: ${action.toUpperCase()}
 Account: ${userAccount}
-Timestamp: ${timestamp}
+${extraLines}Timestamp: ${timestamp}
 App: Anoma Counter dApp
 
 By signing this message, I authorize the execution of this action on the Anoma network.`;
@@ -114,17 +131,19 @@ By signing this message, I authorize the emission of an empty transaction to the
     userAccount: string,
     signature: string,
     signedMessage: string,
-    timestamp: string
-  ): Promise<EmitTransactionResponse> {
-    const request: EmitTransactionRequest = {
+    timestamp: string,
+    shape: TransactionShape = {}
+  ): Promise<EmitRealTransactionResponse> {
+    const request: EmitTransactionRequest & TransactionShape = {
       user_account: userAccount,
       signature,
       signed_message: signedMessage,
       timestamp,
+      ...shape,
     };
 
     try {
-      const response = await apiClient.post<EmitTransactionResponse>('/emit-real-transaction', request);
+      const response = await apiClient.post<EmitRealTransactionResponse>('/emit-real-transaction', request);
       return response.data;
     } catch (error) {
       if (axios.isAxiosError(error) && error.response) {
@@ -191,6 +210,79 @@ By signing this message, I authorize the emission of an empty transaction to the
     }
   }
 
+  /**
+   * Generate message for an operation of a registered app. Operations on an existing
+   * resource sign its commitment; transfers also sign the recipient's cnk.
+   */
+  static generateAppMessage(
+    operation: AppOperation,
+    userAccount: string,
+    timestamp: string,
+    options: { commitment?: string; recipientCnk?: string } = {}
+  ): string {
+    const extraLines = [
+      options.commitment ? `Resource: ${options.commitment}\n` : '',
+      options.recipientCnk ? `Recipient: ${options.recipientCnk}\n` : '',
+    ].join('');
+    return `Anoma Counter Authorization
+
+Action: ${operation.toUpperCase()}
+Account: ${userAccount}
+${extraLines}Timestamp: ${timestamp}
+App: Anoma Counter dApp
+
+By signing this message, I authorize the execution of this action on the Anoma network.`;
+  }
+
+  /**
+   * List the resources of an app created for an account
+   */
+  static async listAppResources(app: string, userAccount: string): Promise<AppResourceView[]> {
+    try {
+      const response = await apiClient.get<AppResourceView[]>(`/apps/${app}/resources/${userAccount}`);
+      return response.data;
+    } catch (error) {
+      if (axios.isAxiosError(error) && error.response) {
+        const errorMsg = error.response.data?.error || 'Failed to list resources';
+        throw new Error(errorMsg);
+      }
+      throw new Error('Network error occurred');
+    }
+  }
+
+  /**
+   * Send a resource of an app to the owner of a User Key
+   */
+  static async transferAppResource(app: string, request: AppOperationRequest): Promise<AppOperationResponse> {
+    try {
+      const response = await apiClient.post<AppOperationResponse>(`/apps/${app}/transfer`, request);
+      return response.data;
+    } catch (error) {
+      if (axios.isAxiosError(error) && error.response) {
+        const errorMsg = error.response.data?.error || 'Failed to transfer resource';
+        throw new Error(errorMsg);
+      }
+      throw new Error('Network error occurred');
+    }
+  }
+
+  /**
+   * Discovery payloads of transactions executed from `fromBlock` on, for trial
+   * decryption with scanInbox in services/discovery.ts
+   */
+  static async getInboxPayloads(fromBlock: number = 0): Promise<PublishedPayload[]> {
+    try {
+      const response = await apiClient.get<PublishedPayload[]>('/inbox', { params: { from_block: fromBlock } });
+      return response.data;
+    } catch (error) {
+      if (axios.isAxiosError(error) && error.response) {
+        const errorMsg = error.response.data?.error || 'Failed to load discovery payloads';
+        throw new Error(errorMsg);
+      }
+      throw new Error('Network error occurred');
+    }
+  }
+
   // Note: Counter helper methods are removed - use executeCounterAction directly with signatures
 }
 
@@ -439,6 +439,38 @@ export async function decrypt(key: Uint8Array, ciphertext: Uint8Array): Promise<
   return new Uint8Array(decrypted);
 }
 
+/**
+ * Encrypt data to a recipient's static encryption public key (sepk) with a fresh
+ * ephemeral key. Same format as the backend (src/encryption.rs):
+ * ephemeral public key (33 bytes) || IV (12 bytes) || AES-GCM output
+ * @param recipientSepk Recipient's sepk (33 bytes compressed)
+ * @param plaintext Data to encrypt
+ * @returns Ciphertext
+ */
+export async function encryptForRecipient(recipientSepk: Uint8Array, plaintext: Uint8Array): Promise<Uint8Array> {
+  const ephemeral = generateEphemeralEncryptionKeyPair();
+  const key = kdf(diffieHellman(ephemeral.privateKey, recipientSepk), ephemeral.publicKey);
+  const encrypted = await encrypt(key, plaintext);
+
+  const result = new Uint8Array(ephemeral.publicKey.length + encrypted.length);
+  result.set(ephemeral.publicKey, 0);
+  result.set(encrypted, ephemeral.publicKey.length);
+  return result;
+}
+
+/**
+ * Decrypt data produced by encryptForRecipient or by the backend, e.g. the
+ * ciphertext of a transferred resource
+ * @param sesk Recipient's static encryption private key (32 bytes)
+ * @param ciphertext Ephemeral public key || IV || AES-GCM output
+ * @returns Decrypted data
+ */
+export async function decryptFromSender(sesk: Uint8Array, ciphertext: Uint8Array): Promise<Uint8Array> {
+  const ephemeralPublicKey = ciphertext.slice(0, 33);
+  const key = kdf(diffieHellman(sesk, ephemeralPublicKey), ephemeralPublicKey);
+  return decrypt(key, ciphertext.slice(33));
+}
+
 /**
  * Format a key for display (first 8 chars + ... + last 8 chars)
  */
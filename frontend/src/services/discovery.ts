@@ -0,0 +1,67 @@
+/**
+ * Resource discovery in the browser. The backend lists the discovery payloads of
+ * executed transactions (GET /inbox); they are trial-decrypted here with the
+ * user's static discovery key, so secret keys never leave the client. Same format
+ * as the backend (src/discovery.rs): the discovery plaintext is
+ * "anoma-discovery:v1" || commitment, the resource plaintext is its JSON encoding.
+ */
+
+import ApiService from './api';
+import { decryptFromSender, bytesToHex, hexToBytes } from './cryptography';
+import type { InboxEntry, PublishedPayload } from '../types/api';
+
+const DISCOVERY_MARKER = new TextEncoder().encode('anoma-discovery:v1');
+
+function fromHex(hex: string): Uint8Array {
+  return hexToBytes(hex.startsWith('0x') ? hex.slice(2) : hex);
+}
+
+/**
+ * The commitment announced to the owner of sdsk, or null if the payload is
+ * addressed to someone else
+ */
+async function trialDecrypt(payload: PublishedPayload, sdsk: Uint8Array): Promise<string | null> {
+  let plaintext: Uint8Array;
+  try {
+    plaintext = await decryptFromSender(sdsk, fromHex(payload.discovery_ciphertext));
+  } catch {
+    return null;
+  }
+  const marked = DISCOVERY_MARKER.every((byte, i) => plaintext[i] === byte);
+  return marked ? '0x' + bytesToHex(plaintext.slice(DISCOVERY_MARKER.length)) : null;
+}
+
+/**
+ * Scan transactions executed from `fromBlock` on for resources sent to the owner
+ * of sdsk. Resources are decrypted when sesk is given.
+ * @param sdsk Static discovery private key (32 bytes)
+ * @param sesk Static encryption private key (32 bytes), optional
+ */
+export async function scanInbox(sdsk: Uint8Array, sesk?: Uint8Array, fromBlock: number = 0): Promise<InboxEntry[]> {
+  const inbox: InboxEntry[] = [];
+  for (const payload of await ApiService.getInboxPayloads(fromBlock)) {
+    const commitment = await trialDecrypt(payload, sdsk);
+    if (!commitment) {
+      continue;
+    }
+
+    const entry: InboxEntry = {
+      tx_hash: payload.tx_hash,
+      block_number: payload.block_number,
+      commitment,
+      verified_on_chain: payload.created_commitments.includes(commitment),
+    };
+    if (!sesk) {
+      entry.resource_ciphertext = payload.resource_ciphertext;
+    } else {
+      try {
+        const plaintext = await decryptFromSender(sesk, fromHex(payload.resource_ciphertext));
+        entry.resource = JSON.parse(new TextDecoder().decode(plaintext));
+      } catch (error) {
+        entry.error = error instanceof Error ? error.message : 'Failed to decrypt resource';
+      }
+    }
+    inbox.push(entry);
+  }
+  return inbox;
+}
@@ -1,10 +1,23 @@
 // API Types based on existing Rust backend
 export interface ExecuteRequest {
-  action: string; // initialize, increment, decrement
+  action: string; // initialize, increment, decrement, add, reset, close
   user_account: string; // wallet address
   signature: string; // MetaMask signature
   signed_message: string; // The message that was signed
   timestamp: string; // When the signature was created
+  amount?: number; // Required for add; signed as an "Amount: <n>" line
+  counter_id?: string; // Shared counter to act on; signed as a "Counter: <id>" line
+  approvals?: Approval[]; // Co-owner approvals for reset and close
+  owners?: string[]; // Co-owners of a counter being initialized
+  threshold?: number; // Owners that must approve reset and close
+}
+
+// A co-owner's signature over the same action on the same counter
+export interface Approval {
+  user_account: string;
+  signature: string;
+  signed_message: string;
+  timestamp: string;
 }
 
 export interface ExecuteResponse {
@@ -63,4 +76,101 @@ export interface EmitTransactionResponse {
   transaction_data?: any; // ARM transaction data for ethers.js execution
 }
 
-export type CounterAction = 'initialize' | 'increment' | 'decrement';
+// Optional shape of /emit-real-transaction, sent next to the signed fields
+export interface TransactionShape {
+  actions?: number;
+}
+
+export interface TransactionReport {
+  actions: number;
+  compliance_units: number;
+  logic_proofs: number;
+  compliance_proof_bytes: number;
+  logic_proof_bytes: number;
+  delta_proof_bytes: number;
+  ciphertext_bytes: number;
+  app_data_bytes: number;
+  calldata_bytes: number;
+  proving_ms: number;
+  gas_used?: number;
+}
+
+export interface EmitRealTransactionResponse extends EmitTransactionResponse {
+  artifact_hash: string;
+  report: TransactionReport;
+}
+
+export type CounterAction = 'initialize' | 'increment' | 'decrement' | 'add' | 'reset' | 'close';
+
+// Generic /apps endpoints
+export type AppOperation = 'create' | 'transform' | 'consume' | 'transfer';
+
+export interface AppResourceView {
+  app: string;
+  commitment: string;
+  data: any; // decoded by the app, e.g. { value: 3 } for a counter
+  created_in?: string;
+  consumed_in?: string;
+}
+
+// Typed data attached to a created resource; `immediately` blobs may be pruned by
+// indexers once the transaction is executed
+export interface AppDataItem {
+  kind: 'memo' | 'label' | 'external_ref';
+  value: string;
+  deletion_criterion: 'immediately' | 'never';
+}
+
+export interface AppOperationRequest {
+  user_account: string;
+  signature: string;
+  signed_message: string;
+  timestamp: string;
+  commitment?: string;
+  params?: any;
+  recipient_user_key?: string;
+  app_data?: AppDataItem[]; // create and transform only
+  forwarder_call?: ForwarderCall; // create and transform only
+}
+
+// Call from the Protocol Adapter into an external contract, made when the created
+// resource's transaction executes. All fields are hex.
+export interface ForwarderCall {
+  target: string;
+  input: string;
+  expected_output: string;
+}
+
+export interface SentResource {
+  recipient_cnk: string;
+  view: AppResourceView;
+  resource: any; // full ARM resource, needed by the recipient to consume it
+  ciphertext: string; // hex, the resource JSON encrypted to the recipient's sepk
+}
+
+export interface AppOperationResponse {
+  transaction_hash: string;
+  artifact_hash: string;
+  created?: AppResourceView;
+  sent?: SentResource;
+}
+
+// Resource discovery. The backend only lists payloads; they are trial-decrypted
+// in the browser (services/discovery.ts), so secret keys never leave it.
+export interface PublishedPayload {
+  tx_hash: string;
+  block_number?: number;
+  created_commitments: string[];
+  discovery_ciphertext: string; // hex, encrypted to the recipient's sdpk
+  resource_ciphertext: string; // hex, encrypted to the recipient's sepk
+}
+
+export interface InboxEntry {
+  tx_hash: string;
+  block_number?: number;
+  commitment: string;
+  verified_on_chain: boolean;
+  resource?: any;
+  resource_ciphertext?: string; // decrypt with decryptFromSender
+  error?: string;
+}
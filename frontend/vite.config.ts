@@ -6,6 +6,10 @@ export default defineConfig({
   plugins: [react(), tailwindcss()],
   server: {
     port: 5173,
+    fs: {
+      // The test transaction is imported from the repository's fixtures
+      allow: ['..']
+    },
     proxy: {
       '/api': {
         target: 'http://127.0.0.1:3000',
@@ -0,0 +1,318 @@
+// Typed application data attached to created resources.
+//
+// Logic instances carry `appData`, a list of blobs each with a deletion criterion:
+// `immediately` blobs may be dropped by indexers once the transaction is executed,
+// `never` blobs are kept. The backend accepts memos, labels and external references
+// and encodes each as one blob:
+//
+//   version (1) || kind (1) || deletion criterion (1) || 0 || length (u32 LE) || UTF-8 value
+//
+// zero-padded to whole 32-bit words. The criterion is repeated in the header so that
+// a decoded blob can be checked against the instance it was published in.
+//
+// The blobs are committed by the app's logic guest, so apps receive them in
+// `Application::create` and `transform` and place them in their logic witness.
+// `check_committed` confirms the proved instance carries exactly the requested blobs.
+//
+// No guest in arm-risc0 v0.3.0 takes app data as witness input, so no registered
+// app commits any yet and requests with app data are rejected before proving. An
+// on-chain round trip is blocked on such a guest; the encoding itself is covered by
+// the tests below.
+
+use alloy::primitives::hex;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::Transaction as ArmTransaction;
+use arm_risc0::utils;
+use serde::{Deserialize, Serialize};
+
+use crate::counter_audit;
+use crate::forwarder::ForwarderCall;
+
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 8;
+const MAX_VALUE_LEN: usize = 1024;
+const MAX_LABEL_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AppDataKind {
+    Memo,
+    Label,
+    ExternalRef,
+}
+
+impl AppDataKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            AppDataKind::Memo => 0,
+            AppDataKind::Label => 1,
+            AppDataKind::ExternalRef => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            0 => Ok(AppDataKind::Memo),
+            1 => Ok(AppDataKind::Label),
+            2 => Ok(AppDataKind::ExternalRef),
+            other => Err(format!("Unknown app data kind {}", other)),
+        }
+    }
+}
+
+// Same numbering as the Protocol Adapter's DeletionCriterion
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionCriterion {
+    Immediately,
+    Never,
+}
+
+impl DeletionCriterion {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            DeletionCriterion::Immediately => 0,
+            DeletionCriterion::Never => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            0 => Ok(DeletionCriterion::Immediately),
+            1 => Ok(DeletionCriterion::Never),
+            other => Err(format!("Unknown deletion criterion {}", other)),
+        }
+    }
+}
+
+// One item as accepted by the API, e.g.
+// `{"kind": "memo", "value": "rent", "deletion_criterion": "never"}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AppDataItem {
+    pub kind: AppDataKind,
+    pub value: String,
+    pub deletion_criterion: DeletionCriterion,
+}
+
+impl AppDataItem {
+    fn validate(&self) -> Result<(), String> {
+        let max_len = match self.kind {
+            AppDataKind::Label => MAX_LABEL_LEN,
+            AppDataKind::Memo | AppDataKind::ExternalRef => MAX_VALUE_LEN,
+        };
+        if self.value.len() > max_len {
+            return Err(format!("{:?} is longer than {} bytes", self.kind, max_len));
+        }
+        if self.kind != AppDataKind::Memo && self.value.trim().is_empty() {
+            return Err(format!("{:?} must not be empty", self.kind));
+        }
+        Ok(())
+    }
+
+    pub fn encode(&self) -> Result<AppDataBlob, String> {
+        self.validate()?;
+        let value = self.value.as_bytes();
+        let mut bytes = vec![VERSION, self.kind.to_byte(), self.deletion_criterion.to_byte(), 0];
+        bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(value);
+        bytes.resize(bytes.len().div_ceil(4) * 4, 0);
+        Ok(AppDataBlob {
+            deletion_criterion: self.deletion_criterion,
+            bytes,
+        })
+    }
+}
+
+// An encoded item, as it appears in a logic instance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AppDataBlob {
+    pub deletion_criterion: DeletionCriterion,
+    pub bytes: Vec<u8>,
+}
+
+impl AppDataBlob {
+    // Decodes a blob published with `deletion_criterion` and checks that it was
+    // encoded for that criterion.
+    pub fn decode(bytes: &[u8], deletion_criterion: u8) -> Result<AppDataItem, String> {
+        if bytes.len() < HEADER_LEN {
+            return Err(format!("Blob is too short: {} bytes", bytes.len()));
+        }
+        if bytes[0] != VERSION {
+            return Err(format!("Unsupported app data version {}", bytes[0]));
+        }
+        let kind = AppDataKind::from_byte(bytes[1])?;
+        let encoded_criterion = DeletionCriterion::from_byte(bytes[2])?;
+        if encoded_criterion.to_byte() != deletion_criterion {
+            return Err(format!(
+                "Blob was encoded as {:?} but published with deletion criterion {}",
+                encoded_criterion, deletion_criterion
+            ));
+        }
+
+        let len = u32::from_le_bytes(bytes[4..8].try_into().expect("4 bytes")) as usize;
+        let value = bytes
+            .get(HEADER_LEN..HEADER_LEN + len)
+            .ok_or_else(|| format!("Blob declares {} bytes but holds {}", len, bytes.len() - HEADER_LEN))?;
+        if bytes[HEADER_LEN + len..].iter().any(|&b| b != 0) {
+            return Err("Blob padding is not zero".to_string());
+        }
+        let item = AppDataItem {
+            kind,
+            value: String::from_utf8(value.to_vec()).map_err(|_| "Blob value is not UTF-8".to_string())?,
+            deletion_criterion: encoded_criterion,
+        };
+        item.validate()?;
+        Ok(item)
+    }
+}
+
+pub fn encode_all(items: &[AppDataItem]) -> Result<Vec<AppDataBlob>, String> {
+    items.iter().map(AppDataItem::encode).collect()
+}
+
+// Checks that the logic instance of `created` in a proved transaction carries
+// exactly `expected`. Without requested app data there is nothing to check; apps
+// may publish blobs of their own.
+pub fn check_committed(tx: &ArmTransaction, created: &Resource, expected: &[AppDataBlob]) -> Result<(), String> {
+    if expected.is_empty() {
+        return Ok(());
+    }
+    let commitment = created.commitment();
+    let input = tx
+        .actions
+        .iter()
+        .flat_map(|action| action.logic_verifier_inputs.iter())
+        .find(|input| input.tag == commitment)
+        .ok_or_else(|| "Transaction has no logic proof for the created resource".to_string())?;
+
+    let committed: Vec<(u8, Vec<u8>)> = input
+        .app_data
+        .iter()
+        .map(|blob| (blob.deletion_criterion as u8, utils::words_to_bytes(&blob.blob).to_vec()))
+        .collect();
+    let expected: Vec<(u8, Vec<u8>)> = expected
+        .iter()
+        .map(|blob| (blob.deletion_criterion.to_byte(), blob.bytes.clone()))
+        .collect();
+    if committed != expected {
+        return Err(format!(
+            "Logic instance carries {} app data blobs that do not match the {} requested",
+            committed.len(),
+            expected.len()
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct DecodedBlob {
+    pub tag: String,
+    pub is_consumed: bool,
+    pub index: usize,
+    pub deletion_criterion: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<AppDataItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarder_call: Option<ForwarderCall>,
+    // Blobs that are not typed app data, or fail verification, are returned raw
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Extracts and decodes the app data of every logic instance of an executed
+// Protocol Adapter transaction.
+pub async fn decode_transaction(tx_hash: &str) -> Result<Vec<DecodedBlob>, String> {
+    let executed = counter_audit::executed_call(tx_hash).await?;
+
+    let mut decoded = vec![];
+    for action in &executed.call.transaction.actions {
+        for input in &action.logicVerifierInputs {
+            let instance = &input.instance;
+            for (index, blob) in instance.appData.iter().enumerate() {
+                let (item, error) = match AppDataBlob::decode(&blob.blob, blob.deletionCriterion) {
+                    Ok(item) => (Some(item), None),
+                    Err(e) => (None, Some(e)),
+                };
+                // A forwarder call leads the blobs of its instance
+                let forwarder_call = match (&item, index) {
+                    (None, 0) => ForwarderCall::from_blob(&blob.blob).ok(),
+                    _ => None,
+                };
+                let error = error.filter(|_| forwarder_call.is_none());
+                decoded.push(DecodedBlob {
+                    tag: format!("0x{}", hex::encode(instance.tag)),
+                    is_consumed: instance.isConsumed,
+                    index,
+                    deletion_criterion: blob.deletionCriterion,
+                    raw: error.as_ref().map(|_| format!("0x{}", hex::encode(&blob.blob))),
+                    item,
+                    forwarder_call,
+                    error,
+                });
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: AppDataKind, value: &str, deletion_criterion: DeletionCriterion) -> AppDataItem {
+        AppDataItem {
+            kind,
+            value: value.to_string(),
+            deletion_criterion,
+        }
+    }
+
+    #[test]
+    fn items_round_trip_through_logic_instance_words() {
+        let items = [
+            item(AppDataKind::Memo, "rent", DeletionCriterion::Never),
+            item(AppDataKind::Memo, "", DeletionCriterion::Immediately),
+            item(AppDataKind::Label, "groceries", DeletionCriterion::Immediately),
+            item(AppDataKind::ExternalRef, "ipfs://bafy", DeletionCriterion::Never),
+        ];
+        for (blob, item) in encode_all(&items).unwrap().iter().zip(&items) {
+            assert_eq!(blob.bytes.len() % 4, 0);
+            // As committed by a logic guest and read back from the calldata
+            let published = utils::words_to_bytes(&utils::bytes_to_words(&blob.bytes)).to_vec();
+            assert_eq!(AppDataBlob::decode(&published, blob.deletion_criterion.to_byte()).unwrap(), *item);
+        }
+    }
+
+    #[test]
+    fn blob_published_with_another_criterion_is_rejected() {
+        let blob = item(AppDataKind::Memo, "rent", DeletionCriterion::Never).encode().unwrap();
+        let error = AppDataBlob::decode(&blob.bytes, DeletionCriterion::Immediately.to_byte()).unwrap_err();
+        assert!(error.contains("deletion criterion"), "{}", error);
+    }
+
+    #[test]
+    fn malformed_blobs_are_rejected() {
+        let never = DeletionCriterion::Never.to_byte();
+        let blob = item(AppDataKind::Memo, "rent", DeletionCriterion::Never).encode().unwrap();
+
+        assert!(AppDataBlob::decode(&blob.bytes[..HEADER_LEN - 1], never).is_err());
+        let mut unknown_kind = blob.bytes.clone();
+        unknown_kind[1] = 9;
+        assert!(AppDataBlob::decode(&unknown_kind, never).is_err());
+        let mut truncated = blob.bytes.clone();
+        truncated[4] = 200;
+        assert!(AppDataBlob::decode(&truncated, never).is_err());
+        let mut padded = item(AppDataKind::Memo, "rent!", DeletionCriterion::Never).encode().unwrap().bytes;
+        *padded.last_mut().unwrap() = 1;
+        assert!(AppDataBlob::decode(&padded, never).is_err());
+    }
+
+    #[test]
+    fn oversized_and_empty_items_are_rejected() {
+        let long_label = "x".repeat(MAX_LABEL_LEN + 1);
+        assert!(item(AppDataKind::Label, &long_label, DeletionCriterion::Never).encode().is_err());
+        assert!(item(AppDataKind::ExternalRef, " ", DeletionCriterion::Never).encode().is_err());
+    }
+}
@@ -0,0 +1,645 @@
+// Persistent cache of proved transactions.
+//
+// Proving is the expensive step, so every transaction that passes local verification
+// is written to disk, keyed by the keccak256 of its bincode-encoded ARM form, before
+// it is submitted. A failed submission can then be retried without proving again.
+//
+// Layout under ARTIFACT_DIR (default `artifacts/`):
+//   <hash>.bin     bincode-encoded ARM transaction
+//   <hash>.json    metadata plus the EVM Protocol Adapter transaction
+//   <hash>.inputs  bincode-encoded inputs needed to prove the transaction again,
+//                  encrypted with ARTIFACT_INPUTS_KEY since they hold nullifier keys
+//                  (and kept out of the JSON export). Without the key they are not
+//                  stored, and an artifact whose root has moved cannot be re-proved.
+//
+// An artifact is marked submitted only once its transaction is mined and did not
+// revert, since a submitted artifact can never be resubmitted.
+
+use std::path::PathBuf;
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use alloy::network::ReceiptResponse;
+use alloy::primitives::{hex, keccak256, B256};
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::Transaction as ArmTransaction;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{self, BatchedIncrement};
+use crate::counter::{self, CounterTransition};
+use crate::registry::{self, AppOperation, AppResource, Registry};
+use crate::verification::{self, ExpectedImageIds};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Proved,
+    Submitted { tx_hash: String },
+    Failed { error: String },
+    // Proved again against a newer commitment tree root; see the newer artifact.
+    Superseded { artifact_hash: String },
+}
+
+// What is needed to prove a stored transaction again if its commitment tree root is
+// no longer known to the Protocol Adapter.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum ProvingInputs {
+    Test { actions: usize },
+    CounterInit { created: Resource, nf_key: NullifierKey },
+    CounterIncrement { consumed: Resource, created: Resource, nf_key: NullifierKey },
+    CounterIncrementBatch { increments: Vec<BatchedIncrement> },
+    CounterTransition { consumed: Resource, created: Resource, nf_key: NullifierKey, transition: CounterTransition },
+    // `created` is the ephemeral resource that balances the closing action
+    CounterClose { consumed: Resource, created: Resource, nf_key: NullifierKey },
+    // An operation of a registered app, with the resource it created for the caller
+    // and, for a transfer, the one it sent to another user
+    App { app: String, operation: AppOperation, created: Option<AppResource>, sent: Option<Resource> },
+}
+
+impl ProvingInputs {
+    fn kind(&self) -> &'static str {
+        match self {
+            ProvingInputs::Test { .. } => "test",
+            ProvingInputs::CounterInit { .. } => "counter_init",
+            ProvingInputs::CounterIncrement { .. } => "counter_increment",
+            ProvingInputs::CounterIncrementBatch { .. } => "counter_increment_batch",
+            ProvingInputs::CounterTransition { .. } => "counter_transition",
+            ProvingInputs::CounterClose { .. } => "counter_close",
+            ProvingInputs::App { .. } => "app",
+        }
+    }
+
+    // The counters the transaction moves forward. Single-counter artifacts belong to
+    // `user_account`.
+    pub fn counter_updates(&self, user_account: Option<&str>) -> Vec<CounterUpdate> {
+        let single = |transition: &str, consumed: Option<&Resource>, created: &Resource, nf_key: &NullifierKey| {
+            user_account
+                .map(|account| {
+                    vec![CounterUpdate {
+                        user_account: account.to_string(),
+                        transition: transition.to_string(),
+                        consumed: consumed.cloned(),
+                        created: created.clone(),
+                        nf_key: nf_key.clone(),
+                    }]
+                })
+                .unwrap_or_default()
+        };
+        match self {
+            ProvingInputs::Test { .. } | ProvingInputs::App { .. } => vec![],
+            ProvingInputs::CounterInit { created, nf_key } => single("initialize", None, created, nf_key),
+            ProvingInputs::CounterIncrement { consumed, created, nf_key } => {
+                single("increment", Some(consumed), created, nf_key)
+            }
+            ProvingInputs::CounterTransition { consumed, created, nf_key, transition } => {
+                single(transition.action_name(), Some(consumed), created, nf_key)
+            }
+            ProvingInputs::CounterClose { consumed, created, nf_key } => single("close", Some(consumed), created, nf_key),
+            ProvingInputs::CounterIncrementBatch { increments } => increments
+                .iter()
+                .map(|inc| CounterUpdate {
+                    user_account: inc.user_account.clone(),
+                    transition: "increment".to_string(),
+                    consumed: Some(inc.consumed.clone()),
+                    created: inc.created.clone(),
+                    nf_key: inc.nf_key.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    // The app resources the transaction consumes and creates.
+    pub fn app_update(&self, user_account: Option<&str>) -> Option<AppUpdate> {
+        match self {
+            ProvingInputs::App { app, operation, created, sent } => Some(AppUpdate {
+                app: app.clone(),
+                user_account: user_account?.to_string(),
+                operation: operation.clone(),
+                created: created.clone(),
+                sent: sent.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResubmissionPath {
+    // Every commitment tree root is still in the Protocol Adapter's root history,
+    // so the stored proofs were submitted as they are.
+    Reused,
+    // At least one root is unknown, so the transaction was proved again.
+    Reproved,
+}
+
+pub struct ResubmissionOutcome {
+    pub tx_hash: String,
+    pub path: ResubmissionPath,
+    // Artifact that was submitted; differs from the requested one after re-proving.
+    pub artifact_hash: String,
+    pub unknown_roots: Vec<String>,
+    pub counter_updates: Vec<CounterUpdate>,
+    pub app_update: Option<AppUpdate>,
+}
+
+// An operation of a registered app executed by a submitted transaction, with the
+// resource it created.
+pub struct AppUpdate {
+    pub app: String,
+    pub user_account: String,
+    pub operation: AppOperation,
+    pub created: Option<AppResource>,
+    pub sent: Option<Resource>,
+}
+
+// A counter resource created by a submitted transaction. `consumed` is the previous
+// resource of the counter, or `None` for an initialization.
+pub struct CounterUpdate {
+    pub user_account: String,
+    // `initialize` or the name of the counter transition, for the counter history
+    pub transition: String,
+    pub consumed: Option<Resource>,
+    pub created: Resource,
+    pub nf_key: NullifierKey,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArtifactMetadata {
+    pub hash: String,
+    // What the transaction does, e.g. "test", "counter_init", "counter_increment"
+    pub kind: String,
+    pub user_account: Option<String>,
+    pub actions: usize,
+    pub created_at: String,
+    #[serde(flatten)]
+    pub status: ArtifactStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArtifactRecord {
+    pub metadata: ArtifactMetadata,
+    pub evm_transaction: ProtocolAdapter::Transaction,
+}
+
+pub struct ArtifactStore {
+    dir: PathBuf,
+    // AES-256-GCM key for the proving inputs
+    inputs_key: Option<[u8; 32]>,
+}
+
+impl ArtifactStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create artifact directory {}: {}", dir.display(), e))?;
+        Ok(ArtifactStore { dir, inputs_key: None })
+    }
+
+    pub fn with_inputs_key(mut self, key: [u8; 32]) -> Self {
+        self.inputs_key = Some(key);
+        self
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let store = Self::new(std::env::var("ARTIFACT_DIR").unwrap_or_else(|_| "artifacts".to_string()))?;
+        match std::env::var("ARTIFACT_INPUTS_KEY") {
+            Ok(key) => {
+                let key = hex::decode(key.trim_start_matches("0x"))
+                    .ok()
+                    .and_then(|key| <[u8; 32]>::try_from(key).ok())
+                    .ok_or_else(|| "ARTIFACT_INPUTS_KEY must be 32 bytes of hex".to_string())?;
+                Ok(store.with_inputs_key(key))
+            }
+            Err(_) => Ok(store),
+        }
+    }
+
+    // Hashes come from URLs and the command line, so only accept `0x` + 64 hex digits
+    // before turning them into paths.
+    fn check_hash(hash: &str) -> Result<(), String> {
+        let valid = hash.len() == 66
+            && hash.starts_with("0x")
+            && hash[2..].chars().all(|c| c.is_ascii_hexdigit());
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid artifact hash: {}", hash))
+        }
+    }
+
+    fn bin_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.bin", hash))
+    }
+
+    fn json_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", hash))
+    }
+
+    fn inputs_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.inputs", hash))
+    }
+
+    // Persists a proved transaction and returns its content hash. Saving the same
+    // transaction twice keeps the existing record.
+    pub fn save(
+        &self,
+        inputs: &ProvingInputs,
+        user_account: Option<&str>,
+        arm_tx: &ArmTransaction,
+        evm_tx: &ProtocolAdapter::Transaction,
+    ) -> Result<String, String> {
+        let bytes = bincode::serialize(arm_tx)
+            .map_err(|e| format!("Failed to serialize ARM transaction: {}", e))?;
+        let hash = format!("0x{}", hex::encode(keccak256(&bytes)));
+
+        if self.json_path(&hash).exists() {
+            println!("Artifact {} already stored", hash);
+            return Ok(hash);
+        }
+
+        let record = ArtifactRecord {
+            metadata: ArtifactMetadata {
+                hash: hash.clone(),
+                kind: inputs.kind().to_string(),
+                user_account: user_account.map(str::to_string),
+                actions: arm_tx.actions.len(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                status: ArtifactStatus::Proved,
+            },
+            evm_transaction: evm_tx.clone(),
+        };
+
+        std::fs::write(self.bin_path(&hash), &bytes)
+            .map_err(|e| format!("Failed to write artifact {}: {}", hash, e))?;
+        match &self.inputs_key {
+            Some(key) => {
+                let input_bytes = bincode::serialize(inputs)
+                    .map_err(|e| format!("Failed to serialize proving inputs: {}", e))?;
+                std::fs::write(self.inputs_path(&hash), seal_inputs(key, &input_bytes)?)
+                    .map_err(|e| format!("Failed to write artifact {}: {}", hash, e))?;
+            }
+            None => println!("ARTIFACT_INPUTS_KEY is not set, so artifact {} is stored without proving inputs", hash),
+        }
+        self.write_record(&record)?;
+
+        println!("Stored proved transaction artifact {}", hash);
+        Ok(hash)
+    }
+
+    fn write_record(&self, record: &ArtifactRecord) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(record)
+            .map_err(|e| format!("Failed to serialize artifact record: {}", e))?;
+        std::fs::write(self.json_path(&record.metadata.hash), json)
+            .map_err(|e| format!("Failed to write artifact {}: {}", record.metadata.hash, e))
+    }
+
+    pub fn load_record(&self, hash: &str) -> Result<ArtifactRecord, String> {
+        Self::check_hash(hash)?;
+        let json = std::fs::read(self.json_path(hash))
+            .map_err(|_| format!("Artifact {} not found", hash))?;
+        serde_json::from_slice(&json).map_err(|e| format!("Corrupt artifact record {}: {}", hash, e))
+    }
+
+    pub fn load_bincode(&self, hash: &str) -> Result<Vec<u8>, String> {
+        Self::check_hash(hash)?;
+        std::fs::read(self.bin_path(hash)).map_err(|_| format!("Artifact {} not found", hash))
+    }
+
+    pub fn load_inputs(&self, hash: &str) -> Result<ProvingInputs, String> {
+        Self::check_hash(hash)?;
+        let key = self
+            .inputs_key
+            .as_ref()
+            .ok_or_else(|| format!("ARTIFACT_INPUTS_KEY is not set, so the proving inputs of {} cannot be read", hash))?;
+        let sealed = std::fs::read(self.inputs_path(hash))
+            .map_err(|_| format!("Artifact {} has no stored proving inputs", hash))?;
+        let bytes = open_inputs(key, &sealed).map_err(|e| format!("Proving inputs of artifact {}: {}", hash, e))?;
+        bincode::deserialize(&bytes).map_err(|e| format!("Corrupt proving inputs in artifact {}: {}", hash, e))
+    }
+
+    // A submitted transaction is on chain, so its status is final: a later failure
+    // to submit it again must not hide that it went through.
+    pub fn set_status(&self, hash: &str, status: ArtifactStatus) -> Result<(), String> {
+        let mut record = self.load_record(hash)?;
+        if let ArtifactStatus::Submitted { tx_hash } = &record.metadata.status {
+            if record.metadata.status != status {
+                return Err(format!("Artifact {} was already submitted in {}", hash, tx_hash));
+            }
+        }
+        record.metadata.status = status;
+        self.write_record(&record)
+    }
+
+    // Metadata of every stored artifact, newest first.
+    pub fn list(&self) -> Result<Vec<ArtifactMetadata>, String> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read artifact directory: {}", e))?;
+
+        let mut artifacts: Vec<ArtifactMetadata> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| {
+                let hash = entry.path().file_stem()?.to_str()?.to_string();
+                self.load_record(&hash).ok().map(|record| record.metadata)
+            })
+            .collect();
+        artifacts.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(artifacts)
+    }
+}
+
+// Encrypts proving inputs as a random 12-byte nonce followed by the AES-256-GCM
+// ciphertext.
+fn seal_inputs(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = rand::random::<[u8; 12]>();
+    let ciphertext = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Invalid inputs key: {}", e))?
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| format!("Failed to encrypt proving inputs: {}", e))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open_inputs(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < 12 {
+        return Err("sealed inputs are truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(12);
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|e| format!("Invalid inputs key: {}", e))?
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "cannot be decrypted with ARTIFACT_INPUTS_KEY".to_string())
+}
+
+// Submits a transaction to the Protocol Adapter and waits for its receipt. Returns
+// the transaction hash once it is mined without reverting.
+pub async fn execute_confirmed(evm_tx: ProtocolAdapter::Transaction) -> Result<String, String> {
+    let receipt = protocol_adapter()
+        .execute(evm_tx)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to submit: {}", e))?
+        .get_receipt()
+        .await
+        .map_err(|e| format!("Failed to get receipt: {}", e))?;
+    let tx_hash = format!("0x{}", hex::encode(receipt.transaction_hash()));
+    if !receipt.status() {
+        return Err(format!("Transaction {} reverted", tx_hash));
+    }
+    Ok(tx_hash)
+}
+
+// Commitment tree roots the transaction's compliance units were proved against.
+fn commitment_tree_roots(evm_tx: &ProtocolAdapter::Transaction) -> Vec<B256> {
+    let mut roots: Vec<B256> = evm_tx
+        .actions
+        .iter()
+        .flat_map(|action| action.complianceVerifierInputs.iter())
+        .map(|input| input.instance.consumed.commitmentTreeRoot)
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+// Proves a stored transaction again from its inputs, against the current root.
+async fn reprove(
+    store: &ArtifactStore,
+    inputs: ProvingInputs,
+    user_account: Option<String>,
+) -> Result<(String, ProtocolAdapter::Transaction, ProvingInputs), String> {
+    let adapter = protocol_adapter();
+    let verifier_selector = verification::get_verifier_selector(&adapter).await?;
+
+    // Increments, transitions and closes consume stored counters, so they need fresh
+    // Merkle paths
+    let consumed: Vec<&Resource> = match &inputs {
+        ProvingInputs::CounterIncrement { consumed, .. }
+        | ProvingInputs::CounterTransition { consumed, .. }
+        | ProvingInputs::CounterClose { consumed, .. } => vec![consumed],
+        ProvingInputs::CounterIncrementBatch { increments } => increments.iter().map(|inc| &inc.consumed).collect(),
+        ProvingInputs::App { operation, .. } => operation.consumed().map(|c| &c.resource).into_iter().collect(),
+        _ => vec![],
+    };
+    let mut merkle_paths = vec![];
+    for resource in consumed {
+        let commitment = B256::from_slice(resource.commitment().as_bytes());
+        merkle_paths.push(crate::get_merkle_path(&adapter, commitment).await?);
+    }
+
+    let (arm_tx, inputs, expected) = tokio::task::spawn_blocking(move || Ok::<_, String>(match inputs {
+        ProvingInputs::Test { actions } => (
+            arm_risc0::transaction::generate_test_transaction(actions),
+            ProvingInputs::Test { actions },
+            ExpectedImageIds::any_logic(),
+        ),
+        ProvingInputs::CounterInit { .. } => {
+            let (tx, created, nf_key) = app::init::create_init_counter_tx();
+            (tx, ProvingInputs::CounterInit { created, nf_key }, ExpectedImageIds::counter())
+        }
+        ProvingInputs::CounterIncrement { consumed, nf_key, .. } => {
+            let merkle_path = merkle_paths.pop().expect("Merkle path is fetched for increments");
+            let (tx, created) = crate::create_increment_tx_with_merkle_path(consumed.clone(), nf_key.clone(), merkle_path);
+            (tx, ProvingInputs::CounterIncrement { consumed, created, nf_key }, ExpectedImageIds::counter())
+        }
+        ProvingInputs::CounterTransition { consumed, nf_key, transition, .. } => {
+            let merkle_path = merkle_paths.pop().expect("Merkle path is fetched for transitions");
+            let (tx, created) = counter::create_transition_tx_with_merkle_path(
+                consumed.clone(),
+                nf_key.clone(),
+                merkle_path,
+                transition,
+            )?;
+            (tx, ProvingInputs::CounterTransition { consumed, created, nf_key, transition }, ExpectedImageIds::counter())
+        }
+        ProvingInputs::CounterClose { consumed, nf_key, .. } => {
+            let merkle_path = merkle_paths.pop().expect("Merkle path is fetched for closing");
+            let (tx, created) = counter::create_close_tx_with_merkle_path(consumed.clone(), nf_key.clone(), merkle_path)?;
+            (tx, ProvingInputs::CounterClose { consumed, created, nf_key }, ExpectedImageIds::counter())
+        }
+        ProvingInputs::App { app: name, operation, .. } => {
+            let app = Registry::with_defaults().get(&name)?;
+            let proved = registry::prove(app.as_ref(), &operation, merkle_paths.pop())?;
+            let inputs = ProvingInputs::App {
+                app: name,
+                operation,
+                created: proved.created,
+                sent: proved.sent,
+            };
+            (proved.tx, inputs, app.expected_image_ids())
+        }
+        ProvingInputs::CounterIncrementBatch { increments } => {
+            let mut actions = vec![];
+            let mut witnesses = vec![];
+            let mut reproved = vec![];
+            for (inc, merkle_path) in increments.into_iter().zip(merkle_paths) {
+                let (action, witness, created) = crate::create_increment_action_with_merkle_path(
+                    inc.consumed.clone(),
+                    inc.nf_key.clone(),
+                    merkle_path,
+                );
+                actions.push(action);
+                witnesses.push(witness);
+                reproved.push(BatchedIncrement { created, ..inc });
+            }
+            (
+                batch::compose_transaction(actions, &witnesses),
+                ProvingInputs::CounterIncrementBatch { increments: reproved },
+                ExpectedImageIds::counter(),
+            )
+        }
+    }))
+    .await
+    .map_err(|e| format!("Failed to prove transaction again: {}", e))??;
+
+    let evm_tx = ProtocolAdapter::Transaction::from(arm_tx.clone());
+    verification::verify_transaction(&arm_tx, &evm_tx, &expected, Some(verifier_selector))?;
+    let hash = store.save(&inputs, user_account.as_deref(), &arm_tx, &evm_tx)?;
+    Ok((hash, evm_tx, inputs))
+}
+
+// Only proved and failed artifacts are resubmitted. A submitted one is already on
+// chain, and a superseded one was replaced by a newer artifact.
+pub fn ensure_resubmittable(metadata: &ArtifactMetadata) -> Result<(), String> {
+    match &metadata.status {
+        ArtifactStatus::Proved | ArtifactStatus::Failed { .. } => Ok(()),
+        ArtifactStatus::Submitted { tx_hash } => {
+            Err(format!("Artifact {} was already submitted in {}", metadata.hash, tx_hash))
+        }
+        ArtifactStatus::Superseded { artifact_hash } => {
+            Err(format!("Artifact {} was superseded by {}", metadata.hash, artifact_hash))
+        }
+    }
+}
+
+// Submits a stored transaction to the Protocol Adapter and records the outcome.
+//
+// The Protocol Adapter keeps a history of commitment tree roots, so a transaction
+// proved against an older root is still valid as long as `containsRoot` knows it.
+// Only when a root is truly unknown is the transaction proved again.
+pub async fn resubmit(store: &ArtifactStore, hash: &str) -> Result<ResubmissionOutcome, String> {
+    let record = store.load_record(hash)?;
+    ensure_resubmittable(&record.metadata)?;
+
+    println!(
+        "Resubmitting {} artifact {} ({} actions)...",
+        record.metadata.kind, hash, record.metadata.actions
+    );
+
+    let adapter = protocol_adapter();
+    let mut unknown_roots = vec![];
+    for root in commitment_tree_roots(&record.evm_transaction) {
+        let known = adapter
+            .containsRoot(root)
+            .call()
+            .await
+            .map_err(|e| format!("Failed to call containsRoot: {}", e))?;
+        println!("Commitment tree root 0x{} known: {}", hex::encode(root), known);
+        if !known {
+            unknown_roots.push(format!("0x{}", hex::encode(root)));
+        }
+    }
+
+    let user_account = record.metadata.user_account.clone();
+    let (path, artifact_hash, evm_tx, inputs) = if unknown_roots.is_empty() {
+        println!("All roots are in the root history, reusing stored proofs");
+        let inputs = store.load_inputs(hash).ok();
+        (ResubmissionPath::Reused, hash.to_string(), record.evm_transaction, inputs)
+    } else {
+        println!("{} roots are unknown, proving the transaction again", unknown_roots.len());
+        let inputs = store.load_inputs(hash)?;
+        let (new_hash, evm_tx, inputs) = reprove(store, inputs, user_account.clone()).await?;
+        store.set_status(hash, ArtifactStatus::Superseded { artifact_hash: new_hash.clone() })?;
+        (ResubmissionPath::Reproved, new_hash, evm_tx, Some(inputs))
+    };
+
+    match execute_confirmed(evm_tx).await {
+        Ok(tx_hash) => {
+            println!("Resubmitted artifact {} in transaction {} ({:?})", artifact_hash, tx_hash, path);
+            store.set_status(&artifact_hash, ArtifactStatus::Submitted { tx_hash: tx_hash.clone() })?;
+            Ok(ResubmissionOutcome {
+                tx_hash,
+                path,
+                artifact_hash,
+                unknown_roots,
+                counter_updates: inputs
+                    .as_ref()
+                    .map(|inputs| inputs.counter_updates(user_account.as_deref()))
+                    .unwrap_or_default(),
+                app_update: inputs.and_then(|inputs| inputs.app_update(user_account.as_deref())),
+            })
+        }
+        Err(e) => {
+            store.set_status(&artifact_hash, ArtifactStatus::Failed { error: e.clone() })?;
+            Err(format!("Failed to resubmit artifact {}: {}", artifact_hash, e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with(status: ArtifactStatus) -> (ArtifactStore, String) {
+        let dir = std::env::temp_dir().join(format!("artifacts-test-{}-{}", std::process::id(), rand::random::<u64>()));
+        let store = ArtifactStore::new(dir).unwrap();
+        let hash = format!("0x{}", "ab".repeat(32));
+        let record = ArtifactRecord {
+            metadata: ArtifactMetadata {
+                hash: hash.clone(),
+                kind: "test".to_string(),
+                user_account: None,
+                actions: 0,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                status,
+            },
+            evm_transaction: ProtocolAdapter::Transaction {
+                actions: vec![],
+                deltaProof: vec![].into(),
+            },
+        };
+        store.write_record(&record).unwrap();
+        (store, hash)
+    }
+
+    #[test]
+    fn submitted_status_is_never_downgraded() {
+        let submitted = ArtifactStatus::Submitted { tx_hash: "0x01".to_string() };
+        let (store, hash) = store_with(submitted.clone());
+
+        let failed = ArtifactStatus::Failed { error: "nonce too low".to_string() };
+        assert!(store.set_status(&hash, failed).is_err());
+        assert_eq!(store.load_record(&hash).unwrap().metadata.status, submitted);
+    }
+
+    #[test]
+    fn proving_inputs_are_stored_encrypted() {
+        let (store, _) = store_with(ArtifactStatus::Proved);
+        let store = store.with_inputs_key([7; 32]);
+        let hash = format!("0x{}", "cd".repeat(32));
+        let inputs = bincode::serialize(&ProvingInputs::Test { actions: 3 }).unwrap();
+        std::fs::write(store.inputs_path(&hash), seal_inputs(&[7; 32], &inputs).unwrap()).unwrap();
+
+        let stored = std::fs::read(store.inputs_path(&hash)).unwrap();
+        assert!(!stored.windows(inputs.len()).any(|window| window == inputs.as_slice()));
+        assert!(matches!(store.load_inputs(&hash), Ok(ProvingInputs::Test { actions: 3 })));
+        assert!(store.with_inputs_key([8; 32]).load_inputs(&hash).is_err());
+    }
+
+    #[test]
+    fn only_proved_and_failed_artifacts_are_resubmittable() {
+        for (status, resubmittable) in [
+            (ArtifactStatus::Proved, true),
+            (ArtifactStatus::Failed { error: "reverted".to_string() }, true),
+            (ArtifactStatus::Submitted { tx_hash: "0x01".to_string() }, false),
+            (ArtifactStatus::Superseded { artifact_hash: "0x02".to_string() }, false),
+        ] {
+            let (store, hash) = store_with(status);
+            let metadata = store.load_record(&hash).unwrap().metadata;
+            assert_eq!(ensure_resubmittable(&metadata).is_ok(), resubmittable);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+// Batching of counter actions into a single Protocol Adapter transaction.
+//
+// Every `execute` call pays the fixed cost of verifying a transaction, so instead of
+// submitting one transaction per increment, pending actions from many users are
+// collected over a time window (BATCH_WINDOW_MS, default 5000) or until a size limit
+// is reached (BATCH_MAX_SIZE, default 8). The actions are composed into one
+// `Transaction` whose delta proof is generated from the combined delta witnesses,
+// and the result is fanned back to each waiting request.
+//
+// If the batch is rejected before it is broadcast, or is mined and reverts, none of
+// its actions took effect, so every action is submitted on its own and one bad action
+// only fails its own request. A batch that was broadcast but has no receipt may still
+// be mined, so its actions are failed instead of being submitted a second time.
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::hex;
+use arm_risc0::action::Action;
+use arm_risc0::delta_proof::DeltaWitness;
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::artifacts::{ArtifactStatus, ArtifactStore, ProvingInputs};
+use crate::verification::{self, ExpectedImageIds};
+
+#[derive(Clone, Copy)]
+pub struct BatchConfig {
+    pub window: Duration,
+    pub max_size: usize,
+}
+
+impl BatchConfig {
+    pub fn from_env() -> Self {
+        let window_ms = std::env::var("BATCH_WINDOW_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5000);
+        let max_size = std::env::var("BATCH_MAX_SIZE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|size: &usize| *size > 0)
+            .unwrap_or(8);
+        BatchConfig {
+            window: Duration::from_millis(window_ms),
+            max_size,
+        }
+    }
+}
+
+// A counter increment waiting to be batched, with what is needed to prove it again.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BatchedIncrement {
+    pub user_account: String,
+    pub consumed: Resource,
+    pub created: Resource,
+    pub nf_key: NullifierKey,
+}
+
+#[derive(Clone)]
+pub struct BatchJob {
+    // Counter the increment advances; at most one job per counter is pending
+    pub counter_id: String,
+    pub increment: BatchedIncrement,
+    pub action: Action,
+    pub delta_witness: DeltaWitness,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchReceipt {
+    pub tx_hash: String,
+    pub artifact_hash: String,
+    // Number of actions in the transaction this job was submitted in.
+    pub batch_size: usize,
+    // True if the batch was rejected and this job was submitted on its own.
+    pub isolated: bool,
+}
+
+struct PendingJob<J> {
+    job: J,
+    reply: oneshot::Sender<Result<BatchReceipt, String>>,
+}
+
+// Why a batch transaction was not executed.
+#[derive(Debug)]
+enum BatchError {
+    // Not broadcast, or mined and reverted: none of its actions took effect
+    NotExecuted(String),
+    // Broadcast without a receipt: the transaction may still be mined
+    Unknown(String),
+}
+
+#[derive(Clone)]
+pub struct Batcher {
+    sender: mpsc::Sender<PendingJob<BatchJob>>,
+    // Counters with an action in flight. A second action for the same counter would
+    // consume the same resource and make the whole batch revert. One account may own
+    // several counters, so pending work is keyed by counter, not by account.
+    pending_counters: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Batcher {
+    pub fn spawn(config: BatchConfig, artifacts: Arc<ArtifactStore>) -> Self {
+        let (sender, receiver) = mpsc::channel(config.max_size * 4);
+        tokio::spawn(run(receiver, config, artifacts));
+        Batcher {
+            sender,
+            pending_counters: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    pub fn is_pending(&self, counter_id: &str) -> bool {
+        self.pending_counters.lock().unwrap().contains(counter_id)
+    }
+
+    // Queues a proved action and waits until the transaction containing it is executed.
+    pub async fn submit(&self, job: BatchJob) -> Result<BatchReceipt, String> {
+        let counter_id = job.counter_id.clone();
+        if !self.pending_counters.lock().unwrap().insert(counter_id.clone()) {
+            return Err(format!("An action for counter {} is already waiting in a batch", counter_id));
+        }
+
+        let (reply, receiver) = oneshot::channel();
+        let result = match self.sender.send(PendingJob { job, reply }).await {
+            Ok(()) => receiver
+                .await
+                .unwrap_or_else(|_| Err("Batch worker dropped the job".to_string())),
+            Err(_) => Err("Batch worker is not running".to_string()),
+        };
+
+        self.pending_counters.lock().unwrap().remove(&counter_id);
+        result
+    }
+}
+
+async fn run(mut receiver: mpsc::Receiver<PendingJob<BatchJob>>, config: BatchConfig, artifacts: Arc<ArtifactStore>) {
+    let artifacts: &ArtifactStore = &artifacts;
+    while let Some(first) = receiver.recv().await {
+        let jobs = collect_batch(&mut receiver, first, config).await;
+        process_batch(jobs, |jobs| submit_jobs(jobs, artifacts)).await;
+    }
+}
+
+// Collects the jobs of one batch: until `max_size` jobs are queued or the window,
+// which starts with the first job, has passed.
+async fn collect_batch<T>(receiver: &mut mpsc::Receiver<T>, first: T, config: BatchConfig) -> Vec<T> {
+    let deadline = tokio::time::Instant::now() + config.window;
+    let mut jobs = vec![first];
+    while jobs.len() < config.max_size {
+        match tokio::time::timeout_at(deadline, receiver.recv()).await {
+            Ok(Some(job)) => jobs.push(job),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    jobs
+}
+
+async fn process_batch<J, F, Fut>(jobs: Vec<PendingJob<J>>, submit: F)
+where
+    J: Clone,
+    F: Fn(Vec<J>) -> Fut,
+    Fut: Future<Output = Result<(String, String), BatchError>>,
+{
+    let batch_size = jobs.len();
+    println!("Submitting batch of {} counter actions", batch_size);
+
+    let batch: Vec<J> = jobs.iter().map(|pending| pending.job.clone()).collect();
+    match submit(batch).await {
+        Ok((tx_hash, artifact_hash)) => {
+            println!("Batch of {} actions executed in 0x{}", batch_size, tx_hash);
+            for pending in jobs {
+                let _ = pending.reply.send(Ok(BatchReceipt {
+                    tx_hash: format!("0x{}", tx_hash),
+                    artifact_hash: artifact_hash.clone(),
+                    batch_size,
+                    isolated: false,
+                }));
+            }
+        }
+        Err(BatchError::NotExecuted(e)) if batch_size > 1 => {
+            println!("Batch of {} actions failed: {}. Submitting each action on its own", batch_size, e);
+            for (i, pending) in jobs.into_iter().enumerate() {
+                let result = submit(vec![pending.job])
+                    .await
+                    .map(|(tx_hash, artifact_hash)| BatchReceipt {
+                        tx_hash: format!("0x{}", tx_hash),
+                        artifact_hash,
+                        batch_size: 1,
+                        isolated: true,
+                    })
+                    .map_err(|e| match e {
+                        BatchError::NotExecuted(e) | BatchError::Unknown(e) => e,
+                    });
+                if let Err(e) = &result {
+                    println!("Isolated action {} of the batch failed: {}", i, e);
+                }
+                let _ = pending.reply.send(result);
+            }
+        }
+        Err(BatchError::NotExecuted(e) | BatchError::Unknown(e)) => {
+            for pending in jobs {
+                let _ = pending.reply.send(Err(e.clone()));
+            }
+        }
+    }
+}
+
+// Composes proved actions into one transaction with a single delta proof over the
+// combined delta witnesses.
+pub fn compose_transaction(actions: Vec<Action>, witnesses: &[DeltaWitness]) -> ArmTransaction {
+    let delta_witness = DeltaWitness::compress(witnesses);
+    let mut tx = ArmTransaction::create(actions, Delta::Witness(delta_witness));
+    tx.generate_delta_proof();
+    tx
+}
+
+// Composes, verifies, stores and submits the given jobs as one transaction and waits
+// for it to be mined. Returns the transaction hash (hex, no prefix) and artifact hash.
+async fn submit_jobs(jobs: Vec<BatchJob>, artifacts: &ArtifactStore) -> Result<(String, String), BatchError> {
+    let adapter = protocol_adapter();
+    let verifier_selector = verification::get_verifier_selector(&adapter)
+        .await
+        .map_err(BatchError::NotExecuted)?;
+
+    let increments: Vec<BatchedIncrement> = jobs.iter().map(|job| job.increment.clone()).collect();
+    let (arm_tx, evm_tx) = tokio::task::spawn_blocking(move || {
+        let witnesses: Vec<DeltaWitness> = jobs.iter().map(|job| job.delta_witness.clone()).collect();
+        let actions = jobs.into_iter().map(|job| job.action).collect();
+        let arm_tx = compose_transaction(actions, &witnesses);
+        let evm_tx = ProtocolAdapter::Transaction::from(arm_tx.clone());
+        verification::verify_transaction(&arm_tx, &evm_tx, &ExpectedImageIds::counter(), Some(verifier_selector))?;
+        Ok::<_, String>((arm_tx, evm_tx))
+    })
+    .await
+    .map_err(|e| format!("Failed to compose batch transaction: {}", e))
+    .and_then(|result| result)
+    .map_err(BatchError::NotExecuted)?;
+
+    let artifact_hash = artifacts
+        .save(&ProvingInputs::CounterIncrementBatch { increments }, None, &arm_tx, &evm_tx)
+        .map_err(BatchError::NotExecuted)?;
+
+    let result = async {
+        let pending_tx = adapter
+            .execute(evm_tx)
+            .send()
+            .await
+            .map_err(|e| BatchError::NotExecuted(format!("Failed to submit batch transaction: {}", e)))?;
+        let receipt = pending_tx
+            .get_receipt()
+            .await
+            .map_err(|e| BatchError::Unknown(format!("Failed to get batch transaction receipt: {}", e)))?;
+        let tx_hash = hex::encode(receipt.transaction_hash());
+        if receipt.status() {
+            Ok(tx_hash)
+        } else {
+            Err(BatchError::NotExecuted(format!("Batch transaction 0x{} reverted", tx_hash)))
+        }
+    }
+    .await;
+
+    // The transaction has been sent, so a failure to record its status must not make
+    // the caller submit its actions again
+    let status = match &result {
+        Ok(tx_hash) => ArtifactStatus::Submitted { tx_hash: format!("0x{}", tx_hash) },
+        Err(BatchError::NotExecuted(e) | BatchError::Unknown(e)) => ArtifactStatus::Failed { error: e.clone() },
+    };
+    if let Err(e) = artifacts.set_status(&artifact_hash, status) {
+        println!("Failed to update artifact {}: {}", artifact_hash, e);
+    }
+    result.map(|tx_hash| (tx_hash, artifact_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(window_ms: u64, max_size: usize) -> BatchConfig {
+        BatchConfig {
+            window: Duration::from_millis(window_ms),
+            max_size,
+        }
+    }
+
+    #[tokio::test]
+    async fn full_batch_is_flushed_before_the_window_ends() {
+        let (sender, mut receiver) = mpsc::channel(8);
+        for job in 1..=4 {
+            sender.send(job).await.unwrap();
+        }
+        let first = receiver.recv().await.unwrap();
+        let started = tokio::time::Instant::now();
+        let jobs = collect_batch(&mut receiver, first, config(60_000, 3)).await;
+        assert_eq!(jobs, vec![1, 2, 3]);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn window_flushes_a_partial_batch() {
+        let (sender, mut receiver) = mpsc::channel(8);
+        sender.send(2).await.unwrap();
+        let started = tokio::time::Instant::now();
+        let jobs = collect_batch(&mut receiver, 1, config(50, 8)).await;
+        assert_eq!(jobs, vec![1, 2]);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    fn pending(jobs: &[u32]) -> (Vec<PendingJob<u32>>, Vec<oneshot::Receiver<Result<BatchReceipt, String>>>) {
+        jobs.iter()
+            .map(|&job| {
+                let (reply, receiver) = oneshot::channel();
+                (PendingJob { job, reply }, receiver)
+            })
+            .unzip()
+    }
+
+    #[tokio::test]
+    async fn rejected_batch_fails_only_the_bad_action() {
+        let submitted = Mutex::new(vec![]);
+        let (jobs, replies) = pending(&[1, 2, 3]);
+        process_batch(jobs, |jobs: Vec<u32>| {
+            submitted.lock().unwrap().push(jobs.clone());
+            async move {
+                if jobs.contains(&2) {
+                    Err(BatchError::NotExecuted("action 2 reverts".to_string()))
+                } else {
+                    Ok((format!("{:02x}", jobs[0]), "artifact".to_string()))
+                }
+            }
+        })
+        .await;
+
+        let mut results = vec![];
+        for reply in replies {
+            results.push(reply.await.unwrap());
+        }
+        assert!(results[0].as_ref().is_ok_and(|receipt| receipt.isolated && receipt.tx_hash == "0x01"));
+        assert_eq!(results[1].as_ref().unwrap_err(), "action 2 reverts");
+        assert!(results[2].as_ref().is_ok_and(|receipt| receipt.isolated && receipt.batch_size == 1));
+        assert_eq!(*submitted.lock().unwrap(), vec![vec![1, 2, 3], vec![1], vec![2], vec![3]]);
+    }
+
+    #[tokio::test]
+    async fn broadcast_batch_without_receipt_is_not_submitted_again() {
+        let submitted = Mutex::new(0);
+        let (jobs, replies) = pending(&[1, 2]);
+        process_batch(jobs, |_: Vec<u32>| {
+            *submitted.lock().unwrap() += 1;
+            async { Err(BatchError::Unknown("receipt timed out".to_string())) }
+        })
+        .await;
+
+        for reply in replies {
+            assert_eq!(reply.await.unwrap().unwrap_err(), "receipt timed out");
+        }
+        assert_eq!(*submitted.lock().unwrap(), 1);
+    }
+}
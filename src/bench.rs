@@ -0,0 +1,386 @@
+// Benchmarks of proving and of the transaction shapes the backend submits: an empty
+// transaction, a counter initialization, a counter increment and ARM test
+// transactions of N actions.
+//
+// Every case is proved with the prover backend the environment selects
+// (RISC0_DEV_MODE=1 for dev mode, RISC0_PROVER=local for the local prover, Bonsai
+// when BONSAI_API_KEY is set), so backends are compared by running the benchmark
+// once per backend. Each case records the time of its proving steps and of local
+// verification, the peak resident memory of the process and the sizes of
+// `TransactionReport`. With an RPC URL, every case is also submitted to the
+// Protocol Adapter at LOCAL_PROTOCOL_ADAPTER_ADDRESS and its gas used is recorded.
+// The report is written as JSON, for comparison across versions.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::{hex, Address, B256};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::signers::local::PrivateKeySigner;
+use arm_risc0::action::Action;
+use arm_risc0::delta_proof::DeltaWitness;
+use arm_risc0::merkle_path::MerklePath;
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use risc0_zkvm::sha::Digest;
+use serde::Serialize;
+
+use crate::forwarder::ANVIL_DEFAULT_KEY;
+use crate::stress::{TransactionReport, TransactionShape};
+use crate::verification::{self, ExpectedImageIds};
+
+pub const DEFAULT_REPORT_PATH: &str = "bench-report.json";
+
+// Action counts of the test transaction cases, overridden by BENCH_ACTIONS
+const DEFAULT_ACTION_COUNTS: &str = "1,2,4";
+
+type LocalAdapter = ProtocolAdapter::ProtocolAdapterInstance<DynProvider>;
+
+#[derive(Serialize)]
+pub struct BenchReport {
+    pub version: &'static str,
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_url: Option<String>,
+    pub cases: Vec<BenchCase>,
+}
+
+#[derive(Serialize)]
+pub struct BenchCase {
+    pub name: String,
+    // Milliseconds of each proving step and of local verification
+    pub steps_ms: BTreeMap<&'static str, u128>,
+    // Peak resident memory of this process during the case. Proofs generated by an
+    // external prover (r0vm or Bonsai) are not counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss_kb: Option<u64>,
+    pub report: TransactionReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submission_error: Option<String>,
+}
+
+// Timings of one case. Starting a case resets the peak resident memory.
+struct Steps {
+    ms: BTreeMap<&'static str, u128>,
+    proving: Duration,
+}
+
+impl Steps {
+    fn start() -> Self {
+        reset_peak_rss();
+        Steps {
+            ms: BTreeMap::new(),
+            proving: Duration::ZERO,
+        }
+    }
+
+    async fn prove<T: Send + 'static>(
+        &mut self,
+        step: &'static str,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, String> {
+        let started = Instant::now();
+        let output = tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| format!("Proving step {} panicked: {}", step, e))?;
+        let elapsed = started.elapsed();
+        self.ms.insert(step, elapsed.as_millis());
+        self.proving += elapsed;
+        Ok(output)
+    }
+
+    // Records the phases of a transaction proved by `prove_counter_tx`.
+    fn record(&mut self, breakdown: &ProvingBreakdown) {
+        self.ms.insert("compliance_proof", breakdown.compliance.as_millis());
+        self.ms.insert("logic_proofs", breakdown.logic.as_millis());
+        self.ms.insert("delta_proof", breakdown.delta.as_millis());
+        self.proving += breakdown.total();
+    }
+
+    // Verifies the proofs locally, so that the benchmark only reports valid
+    // transactions, and converts the transaction to its EVM form.
+    async fn verify(
+        &mut self,
+        tx: ArmTransaction,
+        expected: ExpectedImageIds,
+    ) -> Result<ProtocolAdapter::Transaction, String> {
+        let started = Instant::now();
+        let evm_tx = tokio::task::spawn_blocking(move || {
+            verification::verify_arm_proofs(&tx, &expected).into_result()?;
+            Ok::<_, String>(ProtocolAdapter::Transaction::from(tx))
+        })
+        .await
+        .map_err(|e| format!("Verification panicked: {}", e))??;
+        self.ms.insert("verify", started.elapsed().as_millis());
+        Ok(evm_tx)
+    }
+}
+
+// Sets the peak resident memory (VmHWM) back to the current one. Linux only.
+fn reset_peak_rss() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+fn peak_rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn prover_backend() -> String {
+    if verification::is_dev_mode() {
+        return "dev".to_string();
+    }
+    match std::env::var("RISC0_PROVER") {
+        Ok(prover) => prover,
+        Err(_) if std::env::var("BONSAI_API_KEY").is_ok() => "bonsai".to_string(),
+        Err(_) => "local".to_string(),
+    }
+}
+
+fn action_counts() -> Result<Vec<usize>, String> {
+    let counts = std::env::var("BENCH_ACTIONS").unwrap_or_else(|_| DEFAULT_ACTION_COUNTS.to_string());
+    counts
+        .split(',')
+        .map(|count| {
+            count
+                .trim()
+                .parse()
+                .map_err(|e| format!("Invalid action count {:?} in BENCH_ACTIONS: {}", count, e))
+        })
+        .collect()
+}
+
+fn connect(rpc_url: &str) -> Result<LocalAdapter, String> {
+    let key = std::env::var("ANVIL_PRIVATE_KEY").unwrap_or_else(|_| ANVIL_DEFAULT_KEY.to_string());
+    let signer: PrivateKeySigner = key.parse().map_err(|e| format!("Invalid ANVIL_PRIVATE_KEY: {}", e))?;
+    let address: Address = std::env::var("LOCAL_PROTOCOL_ADAPTER_ADDRESS")
+        .map_err(|_| "LOCAL_PROTOCOL_ADAPTER_ADDRESS must be set to measure gas".to_string())?
+        .parse()
+        .map_err(|e| format!("Invalid LOCAL_PROTOCOL_ADAPTER_ADDRESS: {}", e))?;
+    let provider = ProviderBuilder::new()
+        .wallet(signer)
+        .connect_http(rpc_url.parse().map_err(|e| format!("Invalid RPC URL {}: {}", rpc_url, e))?)
+        .erased();
+    Ok(ProtocolAdapter::new(address, provider))
+}
+
+// Submits the transaction and returns the gas it used.
+async fn submit(adapter: &LocalAdapter, evm_tx: ProtocolAdapter::Transaction) -> Result<u64, String> {
+    let receipt = adapter
+        .execute(evm_tx)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to submit: {}", e))?
+        .get_receipt()
+        .await
+        .map_err(|e| format!("Failed to get receipt: {}", e))?;
+    if !receipt.status() {
+        return Err(format!("Transaction 0x{} reverted", hex::encode(receipt.transaction_hash())));
+    }
+    Ok(receipt.gas_used())
+}
+
+async fn finish(
+    name: &str,
+    steps: Steps,
+    evm_tx: ProtocolAdapter::Transaction,
+    chain: Option<&LocalAdapter>,
+) -> BenchCase {
+    let mut report = TransactionReport::measure(&evm_tx, steps.proving);
+    let peak_rss_kb = peak_rss_kb();
+    let mut submission_error = None;
+    if let Some(adapter) = chain {
+        match submit(adapter, evm_tx).await {
+            Ok(gas_used) => report.gas_used = Some(gas_used),
+            Err(e) => submission_error = Some(e),
+        }
+    }
+    println!(
+        "{}: proved in {} ms, {} calldata bytes, gas {}",
+        name,
+        report.proving_ms,
+        report.calldata_bytes,
+        report.gas_used.map_or_else(|| "-".to_string(), |gas| gas.to_string())
+    );
+    BenchCase {
+        name: name.to_string(),
+        steps_ms: steps.ms,
+        peak_rss_kb,
+        report,
+        submission_error,
+    }
+}
+
+// The ephemeral counter consumed by an initialization, its nullifier key and the
+// counter it creates, built as `app::init::create_init_counter_tx` builds them so
+// the init proving steps can be timed one by one.
+fn init_counters() -> (Resource, NullifierKey, Resource) {
+    let (nf_key, nk_commitment) = NullifierKey::random_pair();
+    let mut ephemeral = Resource::create(
+        *app::COUNTER_ID,
+        Digest::from_bytes(rand::random::<[u8; 32]>()),
+        1,
+        Digest::default(),
+        true,
+        Digest::from_bytes(rand::random::<[u8; 32]>()),
+        nk_commitment,
+    );
+    ephemeral.set_value_ref(app::convert_counter_to_value_ref(0));
+    let nullifier = ephemeral.nullifier(&nf_key).expect("the key is the ephemeral counter's");
+    let mut counter = ephemeral.clone();
+    counter.is_ephemeral = false;
+    counter.set_nonce(nullifier);
+    counter.reset_randomness();
+    (ephemeral, nf_key, counter)
+}
+
+// Time of each proving phase of a single-action counter transaction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProvingBreakdown {
+    pub compliance: Duration,
+    pub logic: Duration,
+    pub delta: Duration,
+}
+
+impl ProvingBreakdown {
+    pub fn total(&self) -> Duration {
+        self.compliance + self.logic + self.delta
+    }
+}
+
+// Proves a single-action counter transition phase by phase: its compliance unit,
+// its logic proofs, then the delta proof of the transaction wrapping them.
+pub fn prove_counter_tx(
+    consumed: Resource,
+    nf_key: NullifierKey,
+    merkle_path: MerklePath,
+    created: Resource,
+) -> (ArmTransaction, ProvingBreakdown) {
+    let started = Instant::now();
+    let (compliance_unit, rcv) =
+        app::generate_compliance_proof(consumed.clone(), nf_key.clone(), merkle_path, created.clone());
+    let compliance = started.elapsed();
+
+    let started = Instant::now();
+    let logic_verifier_inputs = app::generate_logic_proofs(consumed, nf_key, created);
+    let logic = started.elapsed();
+
+    let started = Instant::now();
+    let action = Action::new(vec![compliance_unit], logic_verifier_inputs);
+    let mut tx = ArmTransaction::create(vec![action], Delta::Witness(DeltaWitness::from_bytes(&rcv)));
+    tx.generate_delta_proof();
+    let delta = started.elapsed();
+
+    (tx, ProvingBreakdown { compliance, logic, delta })
+}
+
+async fn prove_counter_tx_blocking(
+    consumed: Resource,
+    nf_key: NullifierKey,
+    merkle_path: MerklePath,
+    created: Resource,
+) -> Result<(ArmTransaction, ProvingBreakdown), String> {
+    tokio::task::spawn_blocking(move || prove_counter_tx(consumed, nf_key, merkle_path, created))
+        .await
+        .map_err(|e| format!("Proving panicked: {}", e))
+}
+
+// Runs every case and writes the report to `report_path`.
+pub async fn run(report_path: &str, rpc_url: Option<&str>) -> Result<BenchReport, String> {
+    let action_counts = action_counts()?;
+    let chain = rpc_url.map(connect).transpose()?;
+    let chain = chain.as_ref();
+    let mut cases = vec![];
+
+    // Step 1: An empty transaction, for the Protocol Adapter's fixed costs
+    let empty = ProtocolAdapter::Transaction {
+        actions: vec![],
+        deltaProof: vec![].into(),
+    };
+    cases.push(finish("empty", Steps::start(), empty, chain).await);
+
+    // Step 2: Counter initialization, with its compliance unit, logic proofs and
+    // delta proof timed separately, as for the increment below
+    let mut steps = Steps::start();
+    let (ephemeral, nf_key, counter) = init_counters();
+    let (init_tx, breakdown) =
+        prove_counter_tx_blocking(ephemeral, nf_key.clone(), MerklePath::default(), counter.clone()).await?;
+    steps.record(&breakdown);
+    let evm_tx = steps.verify(init_tx, ExpectedImageIds::counter()).await?;
+    let init = finish("init", steps, evm_tx, chain).await;
+    let init_submitted = chain.is_some() && init.submission_error.is_none();
+    cases.push(init);
+
+    // Step 3: Increment of the new counter, with its compliance unit, logic proofs
+    // and delta proof timed separately. On a chain, the Merkle path of the submitted
+    // counter is fetched so the increment can be submitted too.
+    let merkle_path = match chain {
+        Some(adapter) if init_submitted => {
+            crate::get_merkle_path(adapter, B256::from_slice(counter.commitment().as_bytes())).await?
+        }
+        _ => MerklePath::default(),
+    };
+    let mut steps = Steps::start();
+    let next = app::increment::increment_counter(&counter, &nf_key);
+    let (increment_tx, breakdown) = prove_counter_tx_blocking(counter, nf_key, merkle_path, next).await?;
+    steps.record(&breakdown);
+    let evm_tx = steps.verify(increment_tx, ExpectedImageIds::counter()).await?;
+    cases.push(finish("increment", steps, evm_tx, chain).await);
+
+    // Step 4: ARM test transactions of N actions
+    for actions in action_counts {
+        let shape = TransactionShape { actions };
+        let mut steps = Steps::start();
+        let tx = steps.prove("test_transaction", move || shape.generate()).await??;
+        let evm_tx = steps.verify(tx, ExpectedImageIds::any_logic()).await?;
+        cases.push(finish(&format!("test-{}", actions), steps, evm_tx, chain).await);
+    }
+
+    let report = BenchReport {
+        version: env!("CARGO_PKG_VERSION"),
+        backend: prover_backend(),
+        rpc_url: rpc_url.map(str::to_string),
+        cases,
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(|e| format!("Failed to encode report: {}", e))?;
+    std::fs::write(report_path, json).map_err(|e| format!("Failed to write {}: {}", report_path, e))?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakdown_covers_every_phase_of_the_proving_time() {
+        verification::use_dev_mode();
+        let (ephemeral, nf_key, counter) = init_counters();
+
+        let started = Instant::now();
+        let (tx, breakdown) = prove_counter_tx(ephemeral, nf_key, MerklePath::default(), counter);
+        let elapsed = started.elapsed();
+
+        let phases = [
+            ("compliance", breakdown.compliance),
+            ("logic", breakdown.logic),
+            ("delta", breakdown.delta),
+        ];
+        for (phase, time) in phases {
+            assert!(time > Duration::ZERO, "{} phase was not timed", phase);
+        }
+        assert_eq!(breakdown.total(), breakdown.compliance + breakdown.logic + breakdown.delta);
+        assert!(breakdown.total() <= elapsed);
+        assert!(verification::verify_arm_proofs(&tx, &ExpectedImageIds::counter()).into_result().is_ok());
+
+        // A case reports the phases as its proving steps and their sum as its proving time
+        let mut steps = Steps::start();
+        steps.record(&breakdown);
+        let phases: Vec<&str> = steps.ms.keys().copied().collect();
+        assert_eq!(phases, vec!["compliance_proof", "delta_proof", "logic_proofs"]);
+        assert_eq!(steps.proving, breakdown.total());
+    }
+}
@@ -0,0 +1,172 @@
+// Command-line subcommands. Without a subcommand the binary starts the API server.
+//
+//   cargo run -- conformance        Check the ARM -> EVM conversion field by field
+//   cargo run -- resubmit <hash>    Submit a stored proved transaction again
+//   cargo run -- encryption-vectors [path]
+//                                   Check resource encryption against the test vectors
+//   cargo run -- decrypt-resource <sesk-hex> <ciphertext-hex>
+//                                   Decrypt a resource sent to the owner of sesk
+//   cargo run -- inbox <sdsk-hex> [sesk-hex] [from-block]
+//                                   Scan executed transactions for resources sent to sdsk
+//   cargo run -- app-data <tx-hash>  Decode the app data blobs of an executed transaction
+//   cargo run --release -- bench [report-path] [rpc-url]
+//                                   Benchmark proving, calldata and (with rpc-url) gas
+
+use alloy::primitives::hex;
+
+use crate::app_data;
+use crate::artifacts::{self, ArtifactStore};
+use crate::bench;
+use crate::conversion;
+use crate::discovery::{self, PayloadStore};
+use crate::encryption;
+use crate::registry::Registry;
+
+// Runs the subcommand named in `args`, if any, and returns its exit code.
+pub async fn run(args: &[String]) -> Option<i32> {
+    let command = args.get(1)?;
+    let code = match command.as_str() {
+        "conformance" => {
+            let failures = tokio::task::spawn_blocking(conversion::run_conformance)
+                .await
+                .expect("Conformance run panicked");
+            if failures == 0 {
+                println!("All conversion conformance cases passed");
+                0
+            } else {
+                println!("{} conversion conformance cases failed", failures);
+                1
+            }
+        }
+        "resubmit" => {
+            let Some(hash) = args.get(2) else {
+                println!("Usage: resubmit <artifact-hash>");
+                return Some(2);
+            };
+            let result = match ArtifactStore::from_env() {
+                Ok(store) => artifacts::resubmit(&store, hash).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(outcome) => {
+                    println!("Path: {:?}", outcome.path);
+                    if !outcome.unknown_roots.is_empty() {
+                        println!("Unknown roots: {}", outcome.unknown_roots.join(", "));
+                    }
+                    println!("Artifact: {}", outcome.artifact_hash);
+                    println!("Transaction: {}", outcome.tx_hash);
+                    0
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    1
+                }
+            }
+        }
+        "encryption-vectors" => {
+            let path = args.get(2).map(String::as_str).unwrap_or(encryption::DEFAULT_VECTORS_PATH);
+            match encryption::run_vector_checks(path) {
+                Ok(0) => {
+                    println!("All encryption vectors passed");
+                    0
+                }
+                Ok(failures) => {
+                    println!("{} encryption vectors failed", failures);
+                    1
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    1
+                }
+            }
+        }
+        "decrypt-resource" => {
+            let (Some(sesk), Some(ciphertext)) = (args.get(2), args.get(3)) else {
+                println!("Usage: decrypt-resource <sesk-hex> <ciphertext-hex>");
+                return Some(2);
+            };
+            let decode = |value: &str| hex::decode(value).map_err(|e| format!("Invalid hex: {}", e));
+            let result = decode(sesk)
+                .and_then(|sesk| Ok((sesk, decode(ciphertext)?)))
+                .and_then(|(sesk, ciphertext)| encryption::decrypt_resource(&sesk, &ciphertext))
+                .and_then(|resource| serde_json::to_string_pretty(&resource).map_err(|e| e.to_string()));
+            match result {
+                Ok(resource) => {
+                    println!("{}", resource);
+                    0
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    1
+                }
+            }
+        }
+        "inbox" => {
+            let Some(sdsk) = args.get(2) else {
+                println!("Usage: inbox <sdsk-hex> [sesk-hex] [from-block]");
+                return Some(2);
+            };
+            let from_block = args.get(4).and_then(|block| block.parse().ok()).unwrap_or(0);
+            let result = async {
+                let decode = |value: &str| hex::decode(value).map_err(|e| format!("Invalid hex: {}", e));
+                let sdsk = decode(sdsk)?;
+                let sesk = args.get(3).map(|sesk| decode(sesk)).transpose()?;
+                let payloads = PayloadStore::from_env()?;
+                discovery::scan(&payloads, &Registry::with_defaults(), &sdsk, sesk.as_deref(), from_block).await
+            }
+            .await;
+            match result {
+                Ok(inbox) => {
+                    println!("{} resources found", inbox.len());
+                    println!("{}", serde_json::to_string_pretty(&inbox).unwrap_or_default());
+                    0
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    1
+                }
+            }
+        }
+        "app-data" => {
+            let Some(tx_hash) = args.get(2) else {
+                println!("Usage: app-data <tx-hash>");
+                return Some(2);
+            };
+            match app_data::decode_transaction(tx_hash).await {
+                Ok(blobs) => {
+                    let invalid = blobs.iter().filter(|blob| blob.error.is_some()).count();
+                    println!("{} app data blobs, {} not valid typed app data", blobs.len(), invalid);
+                    println!("{}", serde_json::to_string_pretty(&blobs).unwrap_or_default());
+                    0
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    1
+                }
+            }
+        }
+        "bench" => {
+            let report_path = args.get(2).map(String::as_str).unwrap_or(bench::DEFAULT_REPORT_PATH);
+            match bench::run(report_path, args.get(3).map(String::as_str)).await {
+                Ok(report) => {
+                    println!("Wrote {} {} cases to {}", report.cases.len(), report.backend, report_path);
+                    0
+                }
+                Err(e) => {
+                    println!("Benchmark failed: {}", e);
+                    1
+                }
+            }
+        }
+        other => {
+            println!("Unknown subcommand: {}", other);
+            println!(
+                "Available subcommands: conformance, resubmit, \
+                 encryption-vectors, decrypt-resource, inbox, app-data, \
+                 bench"
+            );
+            2
+        }
+    };
+    Some(code)
+}
@@ -0,0 +1,372 @@
+// Composition of actions proved by clients.
+//
+// Each party sends a part: a base64 bincode ARM `Transaction` holding the actions it
+// proved and its delta witness. Over the API every part is signed by its party, so
+// the backend only pays gas for transactions whose parties all asked for them. The backend checks every action on its own, then
+// composes all parts into one transaction whose delta proof is generated over the
+// combined witnesses, verifies it and submits it.
+//
+// A delta proof is a signature under the sum of the parts' rcv values, so delta
+// proofs of separate parts cannot be aggregated. A part that already carries its
+// delta proof is only accepted on its own, as a complete transaction.
+//
+// Failures are reported per action: the part and action index with what is wrong,
+// e.g. a compliance proof that does not verify, a logic verifying key the backend does
+// not serve, or a nullifier that another action or the chain already consumed. Only
+// an unbalanced composition is reported for the transaction as a whole.
+
+use std::collections::HashMap;
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::{hex, keccak256};
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+
+use crate::batch;
+use crate::counter_audit;
+use crate::verification::{self, ExpectedImageIds};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ComposePart {
+    pub party: String,
+    pub transaction: String, // base64 bincode ARM transaction
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ActionError {
+    // `None` for errors of the composed transaction as a whole
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub party: Option<String>,
+    // `None` for errors of a whole part, e.g. one that does not decode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<usize>,
+    pub error: String,
+}
+
+pub enum ComposeFailure {
+    // The submitted parts are at fault
+    Rejected(Vec<ActionError>),
+    // Composition could not be completed, e.g. the chain is unreachable
+    Failed(String),
+}
+
+pub struct DecodedPart {
+    pub party: String,
+    pub tx: ArmTransaction,
+}
+
+impl DecodedPart {
+    fn error(&self, part: usize, action: Option<usize>, error: String) -> ActionError {
+        ActionError {
+            part: Some(part),
+            party: Some(self.party.clone()),
+            action,
+            error,
+        }
+    }
+
+    // Consumed nullifiers and created commitments of each action, hex.
+    fn tags(&self) -> impl Iterator<Item = (usize, String, bool)> + '_ {
+        self.tx.actions.iter().enumerate().flat_map(|(i, action)| {
+            action.compliance_units.iter().flat_map(move |unit| {
+                let instance = unit.get_instance();
+                [
+                    (i, format!("0x{}", hex::encode(instance.consumed_nullifier.as_bytes())), true),
+                    (i, format!("0x{}", hex::encode(instance.created_commitment.as_bytes())), false),
+                ]
+            })
+        })
+    }
+}
+
+fn part_bytes(part: &ComposePart) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(part.transaction.trim())
+        .map_err(|e| format!("transaction is not valid base64: {}", e))
+}
+
+// keccak256 of a part's bincode transaction, which its party signs as a
+// `Transaction: 0x<hash>` line.
+pub fn part_hash(part: &ComposePart) -> Result<String, String> {
+    Ok(format!("0x{}", hex::encode(keccak256(part_bytes(part)?))))
+}
+
+fn decode_part(part: &ComposePart) -> Result<ArmTransaction, String> {
+    let bytes = part_bytes(part)?;
+    let tx: ArmTransaction = bincode::deserialize(&bytes).map_err(|e| format!("invalid transaction: {}", e))?;
+    if tx.actions.is_empty() {
+        return Err("transaction has no actions".to_string());
+    }
+    Ok(tx)
+}
+
+// Decodes every part and checks each action's proofs, and that no two actions
+// consume the same nullifier or create the same commitment.
+pub fn decode_and_check(parts: &[ComposePart], expected: &ExpectedImageIds) -> Result<Vec<DecodedPart>, ComposeFailure> {
+    if parts.is_empty() {
+        return Err(ComposeFailure::Failed("No parts to compose".to_string()));
+    }
+    verification::check_compliance_key(expected).map_err(ComposeFailure::Failed)?;
+
+    let mut errors = vec![];
+    let mut decoded = vec![];
+    for (i, part) in parts.iter().enumerate() {
+        let tx = match decode_part(part) {
+            Ok(tx) => tx,
+            Err(e) => {
+                errors.push(ActionError {
+                    part: Some(i),
+                    party: Some(part.party.clone()),
+                    action: None,
+                    error: e,
+                });
+                continue;
+            }
+        };
+        let part = DecodedPart {
+            party: part.party.clone(),
+            tx,
+        };
+        if matches!(part.tx.delta_proof, Delta::Proof(_)) && parts.len() > 1 {
+            errors.push(part.error(
+                i,
+                None,
+                "carries a delta proof, which cannot be aggregated with other parts; send its delta witness".to_string(),
+            ));
+        }
+        for (j, action) in part.tx.actions.iter().enumerate() {
+            for error in verification::verify_action(action, expected) {
+                errors.push(part.error(i, Some(j), error));
+            }
+        }
+        decoded.push((i, part));
+    }
+
+    // The same tag twice would be rejected by the Protocol Adapter
+    let mut seen: HashMap<String, (usize, usize)> = HashMap::new();
+    for (i, part) in &decoded {
+        for (j, tag, is_consumed) in part.tags() {
+            if let Some((first_part, first_action)) = seen.insert(tag.clone(), (*i, j)) {
+                let kind = if is_consumed { "consumes nullifier" } else { "creates commitment" };
+                errors.push(part.error(
+                    *i,
+                    Some(j),
+                    format!("{} {}, as does part {} action {}", kind, tag, first_part, first_action),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(decoded.into_iter().map(|(_, part)| part).collect())
+    } else {
+        Err(ComposeFailure::Rejected(errors))
+    }
+}
+
+// Checks that no action consumes a nullifier the Protocol Adapter already holds.
+pub async fn check_unspent(parts: &[DecodedPart]) -> Result<(), ComposeFailure> {
+    let mut errors = vec![];
+    for (i, part) in parts.iter().enumerate() {
+        for (j, tag, is_consumed) in part.tags() {
+            if !is_consumed {
+                continue;
+            }
+            if counter_audit::is_nullifier_published(&tag).await.map_err(ComposeFailure::Failed)? {
+                errors.push(part.error(i, Some(j), format!("nullifier {} is already spent", tag)));
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ComposeFailure::Rejected(errors))
+    }
+}
+
+// Composes checked parts into one transaction with an aggregate delta proof. A single
+// part with its own delta proof is taken as it is.
+pub fn compose(mut parts: Vec<DecodedPart>) -> Result<ArmTransaction, ComposeFailure> {
+    let tx = if parts.len() == 1 && matches!(parts[0].tx.delta_proof, Delta::Proof(_)) {
+        parts.remove(0).tx
+    } else {
+        let mut actions = vec![];
+        let mut witnesses = vec![];
+        for part in parts {
+            if let Delta::Witness(witness) = part.tx.delta_proof {
+                witnesses.push(witness);
+            }
+            actions.extend(part.tx.actions);
+        }
+        batch::compose_transaction(actions, &witnesses)
+    };
+
+    if !tx.verify() {
+        return Err(ComposeFailure::Rejected(vec![ActionError {
+            part: None,
+            party: None,
+            action: None,
+            error: "the actions do not balance: the aggregate delta proof does not verify".to_string(),
+        }]));
+    }
+    Ok(tx)
+}
+
+// Checks, composes, verifies and submits the parts. Returns the transaction hash.
+pub async fn compose_and_submit(parts: Vec<ComposePart>, expected: ExpectedImageIds) -> Result<String, ComposeFailure> {
+    let adapter = protocol_adapter();
+    let verifier_selector = verification::get_verifier_selector(&adapter)
+        .await
+        .map_err(ComposeFailure::Failed)?;
+
+    // Step 1: Check every action on its own
+    let check_expected = expected.clone();
+    let decoded = tokio::task::spawn_blocking(move || decode_and_check(&parts, &check_expected))
+        .await
+        .map_err(|e| ComposeFailure::Failed(format!("Failed to check parts: {}", e)))??;
+
+    // Step 2: Check the consumed nullifiers against the chain
+    check_unspent(&decoded).await?;
+
+    // Step 3: Compose and verify the whole transaction
+    let evm_tx = tokio::task::spawn_blocking(move || {
+        let tx = compose(decoded)?;
+        let evm_tx = ProtocolAdapter::Transaction::from(tx.clone());
+        verification::verify_transaction(&tx, &evm_tx, &expected, Some(verifier_selector))
+            .map_err(ComposeFailure::Failed)?;
+        Ok::<_, ComposeFailure>(evm_tx)
+    })
+    .await
+    .map_err(|e| ComposeFailure::Failed(format!("Failed to compose transaction: {}", e)))??;
+
+    // Step 4: Submit
+    let pending_tx = adapter
+        .execute(evm_tx)
+        .send()
+        .await
+        .map_err(|e| ComposeFailure::Failed(format!("Failed to submit composed transaction: {}", e)))?;
+    let receipt = pending_tx
+        .get_receipt()
+        .await
+        .map_err(|e| ComposeFailure::Failed(format!("Failed to get composed transaction receipt: {}", e)))?;
+    let tx_hash = format!("0x{}", hex::encode(receipt.transaction_hash()));
+    if receipt.status() {
+        Ok(tx_hash)
+    } else {
+        Err(ComposeFailure::Failed(format!("Composed transaction {} reverted", tx_hash)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arm_risc0::merkle_path::MerklePath;
+    use crate::counter::CounterTransition;
+
+    fn encode_part(party: &str, tx: &ArmTransaction) -> ComposePart {
+        ComposePart {
+            party: party.to_string(),
+            transaction: BASE64.encode(bincode::serialize(tx).unwrap()),
+        }
+    }
+
+    // A counter increment without a delta proof. Roots are not checked without a
+    // chain, so the default Merkle path is used.
+    fn increment() -> ArmTransaction {
+        verification::use_dev_mode();
+        let (_, counter, nf_key) = app::init::create_init_counter_tx();
+        let (action, witness, _) = crate::counter::create_transition_action_with_merkle_path(
+            counter,
+            nf_key,
+            MerklePath::default(),
+            CounterTransition::Increment,
+        )
+        .unwrap();
+        ArmTransaction::create(vec![action], Delta::Witness(witness))
+    }
+
+    fn check(parts: &[ComposePart]) -> Result<ArmTransaction, ComposeFailure> {
+        decode_and_check(parts, &ExpectedImageIds::counter()).and_then(compose)
+    }
+
+    // Asserts that composition was rejected with an error for `part` and `action`
+    // that contains `needle`.
+    fn assert_rejected(
+        result: Result<ArmTransaction, ComposeFailure>,
+        part: Option<usize>,
+        action: Option<usize>,
+        needle: &str,
+    ) {
+        let errors = match result {
+            Ok(_) => panic!("composition was not rejected"),
+            Err(ComposeFailure::Failed(e)) => panic!("composition failed instead: {}", e),
+            Err(ComposeFailure::Rejected(errors)) => errors,
+        };
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.part == part && e.action == action && e.error.contains(needle)),
+            "expected \"{}\" for part {:?} action {:?}, got {:?}",
+            needle,
+            part,
+            action,
+            errors
+        );
+    }
+
+    #[test]
+    fn two_parties_compose_into_one_verified_transaction() {
+        let tx = check(&[encode_part("alice", &increment()), encode_part("bob", &increment())])
+            .unwrap_or_else(|_| panic!("composing two valid parts failed"));
+        assert_eq!(tx.actions.len(), 2);
+        assert!(verification::verify_arm_proofs(&tx, &ExpectedImageIds::counter()).into_result().is_ok());
+    }
+
+    #[test]
+    fn same_action_twice_is_rejected() {
+        let alice = increment();
+        let result = check(&[encode_part("alice", &alice), encode_part("mallory", &alice)]);
+        assert_rejected(result, Some(1), Some(0), "consumes nullifier");
+    }
+
+    #[test]
+    fn delta_proof_is_rejected_with_other_parts() {
+        let mut proved = increment();
+        proved.generate_delta_proof();
+        let result = check(&[encode_part("alice", &proved), encode_part("bob", &increment())]);
+        assert_rejected(result, Some(0), None, "delta proof");
+    }
+
+    #[test]
+    fn undecodable_part_is_rejected() {
+        let garbage = ComposePart {
+            party: "mallory".to_string(),
+            transaction: "not a transaction".to_string(),
+        };
+        let result = check(&[encode_part("alice", &increment()), garbage]);
+        assert_rejected(result, Some(1), None, "base64");
+    }
+
+    #[test]
+    fn logic_proofs_of_another_action_are_rejected() {
+        let mut mismatched = increment();
+        mismatched.actions[0].logic_verifier_inputs = increment().actions[0].logic_verifier_inputs.clone();
+        let result = check(&[encode_part("mallory", &mismatched)]);
+        assert_rejected(result, Some(0), Some(0), "logic proofs do not verify");
+    }
+
+    #[test]
+    fn witness_of_another_action_does_not_balance() {
+        let Delta::Witness(other_witness) = increment().delta_proof else {
+            panic!("partial transaction has no delta witness");
+        };
+        let unbalanced = ArmTransaction::create(increment().actions, Delta::Witness(other_witness));
+        assert_rejected(check(&[encode_part("mallory", &unbalanced)]), None, None, "do not balance");
+    }
+}
@@ -0,0 +1,316 @@
+// Field-by-field conformance check for the ARM -> EVM transaction conversion.
+//
+// `ProtocolAdapter::Transaction::from` has corrupted proofs before, and those were
+// patched by hand with hard-coded blobs. `diff_conversion` recomputes every field the
+// Protocol Adapter reads from the ARM transaction and reports each difference with
+// its full path, so a regression in the bindings shows up as a precise diff.
+
+use std::fmt;
+
+use alloy::primitives::hex;
+use arm_risc0::action::Action;
+use arm_risc0::action_tree::MerkleTree;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use arm_risc0::utils;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use risc0_zkvm::sha::Digest;
+
+use crate::verification::digest_to_b256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMismatch {
+    // e.g. `actions[0].logicVerifierInputs[1].instance.actionTreeRoot`
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for FieldMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}, got {}", self.path, self.expected, self.actual)
+    }
+}
+
+struct Differ {
+    mismatches: Vec<FieldMismatch>,
+}
+
+impl Differ {
+    fn check(&mut self, path: String, expected: &[u8], actual: &[u8]) {
+        if expected != actual {
+            self.mismatches.push(FieldMismatch {
+                path,
+                expected: format!("0x{}", hex::encode(expected)),
+                actual: format!("0x{}", hex::encode(actual)),
+            });
+        }
+    }
+
+    fn check_value<T: PartialEq + fmt::Debug>(&mut self, path: String, expected: T, actual: T) {
+        if expected != actual {
+            self.mismatches.push(FieldMismatch {
+                path,
+                expected: format!("{:?}", expected),
+                actual: format!("{:?}", actual),
+            });
+        }
+    }
+}
+
+// Tags of an action in the order the action tree is built: the consumed nullifier
+// followed by the created commitment of each compliance unit.
+fn action_tags(action: &Action) -> Vec<(Digest, bool)> {
+    action
+        .compliance_units
+        .iter()
+        .flat_map(|unit| {
+            let instance = unit.get_instance();
+            [
+                (instance.consumed_nullifier, true),
+                (instance.created_commitment, false),
+            ]
+        })
+        .collect()
+}
+
+fn action_tree_root(action: &Action) -> Digest {
+    let leaves = action_tags(action).into_iter().map(|(tag, _)| tag).collect();
+    MerkleTree::new(leaves).root()
+}
+
+// Compares every field of the converted EVM transaction against the ARM transaction.
+pub fn diff_conversion(
+    tx: &ArmTransaction,
+    evm_tx: &ProtocolAdapter::Transaction,
+) -> Vec<FieldMismatch> {
+    let mut d = Differ { mismatches: vec![] };
+
+    d.check_value("actions.length".to_string(), tx.actions.len(), evm_tx.actions.len());
+
+    for (i, (action, evm_action)) in tx.actions.iter().zip(evm_tx.actions.iter()).enumerate() {
+        let prefix = format!("actions[{}]", i);
+
+        d.check_value(
+            format!("{}.complianceVerifierInputs.length", prefix),
+            action.compliance_units.len(),
+            evm_action.complianceVerifierInputs.len(),
+        );
+        for (j, (unit, evm_unit)) in action
+            .compliance_units
+            .iter()
+            .zip(evm_action.complianceVerifierInputs.iter())
+            .enumerate()
+        {
+            let path = format!("{}.complianceVerifierInputs[{}]", prefix, j);
+            let instance = unit.get_instance();
+            let evm_instance = &evm_unit.instance;
+
+            d.check(format!("{}.proof", path), &unit.proof, &evm_unit.proof);
+            d.check(
+                format!("{}.instance.consumed.nullifier", path),
+                instance.consumed_nullifier.as_bytes(),
+                evm_instance.consumed.nullifier.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.consumed.logicRef", path),
+                instance.consumed_logic_ref.as_bytes(),
+                evm_instance.consumed.logicRef.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.consumed.commitmentTreeRoot", path),
+                instance.consumed_commitment_tree_root.as_bytes(),
+                evm_instance.consumed.commitmentTreeRoot.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.created.commitment", path),
+                instance.created_commitment.as_bytes(),
+                evm_instance.created.commitment.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.created.logicRef", path),
+                instance.created_logic_ref.as_bytes(),
+                evm_instance.created.logicRef.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.unitDeltaX", path),
+                utils::words_to_bytes(&instance.delta_x),
+                evm_instance.unitDeltaX.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.unitDeltaY", path),
+                utils::words_to_bytes(&instance.delta_y),
+                evm_instance.unitDeltaY.as_slice(),
+            );
+        }
+
+        let tags = action_tags(action);
+        let root = digest_to_b256(&action_tree_root(action));
+
+        d.check_value(
+            format!("{}.logicVerifierInputs.length", prefix),
+            action.logic_verifier_inputs.len(),
+            evm_action.logicVerifierInputs.len(),
+        );
+        for (j, (input, evm_input)) in action
+            .logic_verifier_inputs
+            .iter()
+            .zip(evm_action.logicVerifierInputs.iter())
+            .enumerate()
+        {
+            let path = format!("{}.logicVerifierInputs[{}]", prefix, j);
+            let evm_instance = &evm_input.instance;
+
+            d.check(format!("{}.proof", path), &input.proof, &evm_input.proof);
+            d.check(
+                format!("{}.verifyingKey", path),
+                input.verifying_key.as_bytes(),
+                evm_input.verifyingKey.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.tag", path),
+                input.tag.as_bytes(),
+                evm_instance.tag.as_slice(),
+            );
+            match tags.iter().find(|(tag, _)| *tag == input.tag) {
+                Some((_, is_consumed)) => d.check_value(
+                    format!("{}.instance.isConsumed", path),
+                    *is_consumed,
+                    evm_instance.isConsumed,
+                ),
+                None => d.mismatches.push(FieldMismatch {
+                    path: format!("{}.instance.tag", path),
+                    expected: "a nullifier or commitment of this action".to_string(),
+                    actual: format!("0x{}", hex::encode(input.tag.as_bytes())),
+                }),
+            }
+            d.check(
+                format!("{}.instance.actionTreeRoot", path),
+                root.as_slice(),
+                evm_instance.actionTreeRoot.as_slice(),
+            );
+            d.check(
+                format!("{}.instance.ciphertext", path),
+                utils::words_to_bytes(&input.cipher),
+                &evm_instance.ciphertext,
+            );
+
+            d.check_value(
+                format!("{}.instance.appData.length", path),
+                input.app_data.len(),
+                evm_instance.appData.len(),
+            );
+            for (k, (blob, evm_blob)) in input.app_data.iter().zip(evm_instance.appData.iter()).enumerate() {
+                d.check_value(
+                    format!("{}.instance.appData[{}].deletionCriterion", path, k),
+                    blob.deletion_criterion as u8,
+                    evm_blob.deletionCriterion,
+                );
+                d.check(
+                    format!("{}.instance.appData[{}].blob", path, k),
+                    utils::words_to_bytes(&blob.blob),
+                    &evm_blob.blob,
+                );
+            }
+        }
+    }
+
+    match &tx.delta_proof {
+        Delta::Proof(proof) => d.check("deltaProof".to_string(), &proof.to_bytes(), &evm_tx.deltaProof),
+        Delta::Witness(_) => d.mismatches.push(FieldMismatch {
+            path: "deltaProof".to_string(),
+            expected: "a generated delta proof".to_string(),
+            actual: "unproved delta witness".to_string(),
+        }),
+    }
+
+    d.mismatches
+}
+
+// Converts an ARM transaction and checks the result round-trips field by field.
+pub fn check_conversion(tx: &ArmTransaction) -> Result<ProtocolAdapter::Transaction, Vec<FieldMismatch>> {
+    let evm_tx = ProtocolAdapter::Transaction::from(tx.clone());
+    let mismatches = diff_conversion(tx, &evm_tx);
+    if mismatches.is_empty() {
+        Ok(evm_tx)
+    } else {
+        Err(mismatches)
+    }
+}
+
+pub struct ConformanceCase {
+    pub name: String,
+    pub build: Box<dyn Fn() -> ArmTransaction>,
+}
+
+// Transactions the conversion is checked against: the ARM test transactions and the
+// counter initialization transaction.
+pub fn conformance_cases() -> Vec<ConformanceCase> {
+    let mut cases: Vec<ConformanceCase> = (1..=2)
+        .map(|n| ConformanceCase {
+            name: format!("generate_test_transaction({})", n),
+            build: Box::new(move || arm_risc0::transaction::generate_test_transaction(n)),
+        })
+        .collect();
+    cases.push(ConformanceCase {
+        name: "create_init_counter_tx".to_string(),
+        build: Box::new(|| app::init::create_init_counter_tx().0),
+    });
+    cases
+}
+
+// Runs every conformance case and prints a diff per failing case. Returns the
+// number of failing cases.
+pub fn run_conformance() -> usize {
+    let mut failures = 0;
+    for case in conformance_cases() {
+        println!("Checking conversion for {}...", case.name);
+        let tx = (case.build)();
+        match check_conversion(&tx) {
+            Ok(_) => println!("  ok ({} actions)", tx.actions.len()),
+            Err(mismatches) => {
+                failures += 1;
+                println!("  FAILED with {} mismatched fields:", mismatches.len());
+                for mismatch in mismatches {
+                    println!("    {}", mismatch);
+                }
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conformance_cases_convert_field_by_field() {
+        crate::verification::use_dev_mode();
+        assert_eq!(run_conformance(), 0);
+    }
+
+    #[test]
+    fn corrupted_delta_proof_is_reported() {
+        crate::verification::use_dev_mode();
+        let tx = arm_risc0::transaction::generate_test_transaction(1);
+        let mut evm_tx = check_conversion(&tx).expect("test transaction converts");
+        let mut delta_proof = evm_tx.deltaProof.to_vec();
+        delta_proof[0] ^= 1;
+        evm_tx.deltaProof = delta_proof.into();
+
+        let mismatches = diff_conversion(&tx, &evm_tx);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].path, "deltaProof");
+    }
+
+    #[test]
+    fn missing_action_is_reported() {
+        crate::verification::use_dev_mode();
+        let tx = arm_risc0::transaction::generate_test_transaction(2);
+        let mut evm_tx = check_conversion(&tx).expect("test transaction converts");
+        evm_tx.actions.pop();
+
+        let mismatches = diff_conversion(&tx, &evm_tx);
+        assert!(mismatches.iter().any(|mismatch| mismatch.path == "actions.length"));
+    }
+}
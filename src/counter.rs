@@ -0,0 +1,450 @@
+// Counter transitions beyond initialize and +1.
+//
+// Decrement, add-by-N and reset are built on the same pipeline as
+// `create_increment_tx_with_merkle_path`: the stored counter resource is consumed,
+// a new counter resource with the updated value is created, and the action carries
+// a compliance proof plus the counter logic proofs.
+//
+// Closing a counter consumes its final resource and creates an ephemeral copy
+// instead of a new counter, so the transaction stays balanced while nothing
+// spendable is left behind.
+//
+// The logic proofs come from the counter-app guest of arm-risc0 v0.3.0
+// (examples/counter_app), which constrains the created counter to the consumed value
+// plus one. Only initialization and increment satisfy it. Dev mode does not help:
+// RISC0_DEV_MODE still executes the guest and only fakes the seal, so the guest's
+// assertion fails for any other transition. Decrement, add, reset, close and
+// transfer are therefore refused before proving until a counter logic guest that
+// encodes each transition is part of this repository. Their resources are still
+// built here, so the transitions themselves are tested.
+
+use arm_risc0::action::Action;
+use arm_risc0::delta_proof::DeltaWitness;
+use arm_risc0::merkle_path::MerklePath;
+use arm_risc0::nullifier_key::{NullifierKey, NullifierKeyCommitment};
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::AppDataBlob;
+use crate::registry::{AppResource, Application};
+
+// Decodes the counter value of a counter resource. The counter app stores the value
+// little-endian in the first 16 bytes of `value_ref`.
+pub fn decode_counter_value(resource: &Resource) -> Result<u128, String> {
+    let logic_ref: &[u8] = resource.logic_ref.as_ref();
+    if logic_ref != app::COUNTER_ID.as_bytes() {
+        return Err("Resource is not a counter".to_string());
+    }
+    let bytes: [u8; 16] = resource
+        .value_ref
+        .get(0..16)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Counter value_ref is shorter than 16 bytes".to_string())?;
+    Ok(u128::from_le_bytes(bytes))
+}
+
+// Counter value for logging and arithmetic on resources known to be counters.
+pub fn counter_value(resource: &Resource) -> u128 {
+    decode_counter_value(resource).unwrap_or(0)
+}
+
+// Typed view of a counter resource.
+pub struct CounterState {
+    pub value: u128,
+    pub commitment: Digest,
+    pub nullifier: Digest,
+}
+
+impl CounterState {
+    pub fn decode(resource: &Resource, nf_key: &NullifierKey) -> Result<Self, String> {
+        Ok(CounterState {
+            value: decode_counter_value(resource)?,
+            commitment: resource.commitment(),
+            nullifier: resource
+                .nullifier(nf_key)
+                .ok_or_else(|| "Nullifier key does not match the counter resource".to_string())?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "operation", content = "amount", rename_all = "snake_case")]
+pub enum CounterTransition {
+    Increment,
+    Decrement,
+    Add(u128),
+    Reset,
+}
+
+impl CounterTransition {
+    // Name used in the `Action:` line of the signed message.
+    pub fn action_name(&self) -> &'static str {
+        match self {
+            CounterTransition::Increment => "increment",
+            CounterTransition::Decrement => "decrement",
+            CounterTransition::Add(_) => "add",
+            CounterTransition::Reset => "reset",
+        }
+    }
+
+    pub fn apply(&self, value: u128) -> Result<u128, String> {
+        match self {
+            CounterTransition::Increment => value
+                .checked_add(1)
+                .ok_or_else(|| "Counter overflow".to_string()),
+            CounterTransition::Decrement => value
+                .checked_sub(1)
+                .ok_or_else(|| "Counter underflow: cannot decrement a counter at 0".to_string()),
+            CounterTransition::Add(amount) => value
+                .checked_add(*amount)
+                .ok_or_else(|| format!("Counter overflow: {} + {}", value, amount)),
+            CounterTransition::Reset => Ok(0),
+        }
+    }
+}
+
+// Name of the transition a transform with the given params runs, as recorded in
+// the counter history.
+pub fn transition_name(params: &str) -> &'static str {
+    serde_json::from_str::<CounterTransition>(params)
+        .map(|transition| transition.action_name())
+        .unwrap_or("transform")
+}
+
+// The message the frontend asks the user to sign for a counter action; mirrors
+// `ApiService.generateSigningMessage`.
+pub fn signing_message(action: &str, user_account: &str, timestamp: &str) -> String {
+    format!(
+        "Anoma Counter Authorization\n\nAction: {}\nAccount: {}\nTimestamp: {}\nApp: Anoma Counter dApp\n\nBy signing this message, I authorize the execution of this action on the Anoma network.",
+        action.to_uppercase(),
+        user_account,
+        timestamp
+    )
+}
+
+// A counter transition the user signed. The message follows the frontend's
+// `generateSigningMessage` format; add-by-N additionally signs an `Amount:` line so
+// the amount cannot be changed after signing.
+pub struct CounterIntent {
+    pub transition: CounterTransition,
+}
+
+impl CounterIntent {
+    pub fn new(action: &str, amount: Option<u128>) -> Result<Self, String> {
+        let transition = match (action, amount) {
+            ("increment", None) => CounterTransition::Increment,
+            ("decrement", None) => CounterTransition::Decrement,
+            ("add", Some(amount)) => CounterTransition::Add(amount),
+            ("add", None) => return Err("The add action requires an amount".to_string()),
+            ("reset", None) => CounterTransition::Reset,
+            (action, Some(_)) if ["increment", "decrement", "reset"].contains(&action) => {
+                return Err(format!("The {} action does not take an amount", action))
+            }
+            (other, _) => return Err(format!("Unknown counter action: {}", other)),
+        };
+        Ok(CounterIntent { transition })
+    }
+
+    // Checks the parts of the signed message specific to this intent; the action,
+    // account and timestamp lines are checked by `verify_message_content`.
+    pub fn verify_message(&self, message: &str) -> Result<(), String> {
+        if let CounterTransition::Add(amount) = self.transition {
+            let amount_line = format!("Amount: {}", amount);
+            if !message.lines().any(|line| line.trim() == amount_line) {
+                return Err("Message does not contain the expected amount".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+// Derives the next counter resource, mirroring `app::increment::increment_counter`:
+// the nonce is the consumed counter's nullifier and the randomness is fresh.
+pub fn transition_counter(
+    counter_resource: &Resource,
+    counter_nf_key: &NullifierKey,
+    transition: CounterTransition,
+) -> Result<Resource, String> {
+    let new_value = transition.apply(counter_value(counter_resource))?;
+    let nullifier = counter_resource
+        .nullifier(counter_nf_key)
+        .ok_or_else(|| "Nullifier key does not match the counter resource".to_string())?;
+
+    let mut new_counter = counter_resource.clone();
+    new_counter.set_nonce(nullifier);
+    new_counter.set_value_ref(app::convert_counter_to_value_ref(new_value));
+    new_counter.reset_randomness();
+    Ok(new_counter)
+}
+
+// The resource created when a counter is closed: an ephemeral copy of the final
+// counter with the same quantity, so the closing action is balanced.
+pub fn closing_resource(counter_resource: &Resource, counter_nf_key: &NullifierKey) -> Result<Resource, String> {
+    let nullifier = counter_resource
+        .nullifier(counter_nf_key)
+        .ok_or_else(|| "Nullifier key does not match the counter resource".to_string())?;
+
+    let mut ephemeral = counter_resource.clone();
+    ephemeral.is_ephemeral = true;
+    ephemeral.set_nonce(nullifier);
+    ephemeral.reset_randomness();
+    Ok(ephemeral)
+}
+
+// The resource created when a counter is handed to another user: the same counter
+// value, bound to the recipient's nullifier key commitment.
+pub fn transferred_resource(
+    counter_resource: &Resource,
+    counter_nf_key: &NullifierKey,
+    recipient: &NullifierKeyCommitment,
+) -> Result<Resource, String> {
+    let nullifier = counter_resource
+        .nullifier(counter_nf_key)
+        .ok_or_else(|| "Nullifier key does not match the counter resource".to_string())?;
+
+    let mut transferred = counter_resource.clone();
+    transferred.nk_commitment = recipient.clone();
+    transferred.set_nonce(nullifier);
+    transferred.reset_randomness();
+    Ok(transferred)
+}
+
+// Rejects operations the counter-app guest cannot prove. The guest runs in dev mode
+// too, so this does not depend on RISC0_DEV_MODE.
+fn check_guest_support(operation: &str) -> Result<(), String> {
+    if operation == "increment" {
+        return Ok(());
+    }
+    Err(format!(
+        "The counter logic guest only proves increments; {} needs a counter guest that encodes it",
+        operation
+    ))
+}
+
+// Proves one counter action consuming `counter_resource` and creating `created`.
+fn prove_counter_action(
+    counter_resource: Resource,
+    counter_nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+    created: Resource,
+) -> (Action, DeltaWitness) {
+    let (compliance_unit, rcv) = app::generate_compliance_proof(
+        counter_resource.clone(),
+        counter_nf_key.clone(),
+        merkle_path,
+        created.clone(),
+    );
+    let logic_verifier_inputs = app::generate_logic_proofs(counter_resource, counter_nf_key, created);
+
+    let action = Action::new(vec![compliance_unit], logic_verifier_inputs);
+    (action, DeltaWitness::from_bytes(&rcv))
+}
+
+pub fn create_transition_action_with_merkle_path(
+    counter_resource: Resource,
+    counter_nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+    transition: CounterTransition,
+) -> Result<(Action, DeltaWitness, Resource), String> {
+    check_guest_support(transition.action_name())?;
+    let new_counter = transition_counter(&counter_resource, &counter_nf_key, transition)?;
+
+    println!(
+        "Counter {:?}: {} -> {}",
+        transition,
+        counter_value(&counter_resource),
+        counter_value(&new_counter)
+    );
+
+    let (action, delta_witness) =
+        prove_counter_action(counter_resource, counter_nf_key, merkle_path, new_counter.clone());
+    Ok((action, delta_witness, new_counter))
+}
+
+pub fn create_transition_tx_with_merkle_path(
+    counter_resource: Resource,
+    counter_nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+    transition: CounterTransition,
+) -> Result<(ArmTransaction, Resource), String> {
+    let (action, delta_witness, new_counter) =
+        create_transition_action_with_merkle_path(counter_resource, counter_nf_key, merkle_path, transition)?;
+    let mut tx = ArmTransaction::create(vec![action], Delta::Witness(delta_witness));
+    tx.generate_delta_proof();
+    Ok((tx, new_counter))
+}
+
+// Builds the transaction that closes a counter. Returns it with the ephemeral
+// resource it creates.
+pub fn create_close_tx_with_merkle_path(
+    counter_resource: Resource,
+    counter_nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+) -> Result<(ArmTransaction, Resource), String> {
+    check_guest_support("close")?;
+    let ephemeral = closing_resource(&counter_resource, &counter_nf_key)?;
+    println!("Closing counter at value {}", counter_value(&counter_resource));
+
+    let (action, delta_witness) = prove_counter_action(counter_resource, counter_nf_key, merkle_path, ephemeral.clone());
+    let mut tx = ArmTransaction::create(vec![action], Delta::Witness(delta_witness));
+    tx.generate_delta_proof();
+    Ok((tx, ephemeral))
+}
+
+pub const COUNTER_APP: &str = "counter";
+
+// The counter as a registered application. Create initializes a counter, transform
+// takes a `CounterTransition` as params (e.g. `{"operation": "add", "amount": 5}`),
+// consume closes the counter and transfer hands it to another user.
+pub struct CounterApp;
+
+impl Application for CounterApp {
+    fn name(&self) -> &'static str {
+        COUNTER_APP
+    }
+
+    fn logic_ref(&self) -> Digest {
+        *app::COUNTER_ID
+    }
+
+    fn decode(&self, resource: &Resource) -> Result<serde_json::Value, String> {
+        Ok(serde_json::json!({ "value": decode_counter_value(resource)? }))
+    }
+
+    // The counter guest does not take app data, so `commits_app_data` stays false and
+    // the registry never passes any.
+    fn create(
+        &self,
+        _params: &serde_json::Value,
+        _app_data: &[AppDataBlob],
+    ) -> Result<(ArmTransaction, AppResource), String> {
+        let (tx, resource, nf_key) = app::init::create_init_counter_tx();
+        Ok((tx, AppResource { resource, nf_key }))
+    }
+
+    fn transform(
+        &self,
+        consumed: &AppResource,
+        merkle_path: MerklePath<32>,
+        params: &serde_json::Value,
+        _app_data: &[AppDataBlob],
+    ) -> Result<(ArmTransaction, Resource), String> {
+        let transition: CounterTransition = serde_json::from_value(params.clone())
+            .map_err(|e| format!("Invalid counter transition: {}", e))?;
+        create_transition_tx_with_merkle_path(consumed.resource.clone(), consumed.nf_key.clone(), merkle_path, transition)
+    }
+
+    fn consume(
+        &self,
+        consumed: &AppResource,
+        merkle_path: MerklePath<32>,
+    ) -> Result<(ArmTransaction, Resource), String> {
+        create_close_tx_with_merkle_path(consumed.resource.clone(), consumed.nf_key.clone(), merkle_path)
+    }
+
+    fn transfer(
+        &self,
+        consumed: &AppResource,
+        merkle_path: MerklePath<32>,
+        recipient: &NullifierKeyCommitment,
+    ) -> Result<(ArmTransaction, Resource), String> {
+        // Refused until a counter guest permits an owner change at equal value; the
+        // counter-app guest requires the value to grow by one
+        check_guest_support("transfer")?;
+        let transferred = transferred_resource(&consumed.resource, &consumed.nf_key, recipient)?;
+        let (action, delta_witness) =
+            prove_counter_action(consumed.resource.clone(), consumed.nf_key.clone(), merkle_path, transferred.clone());
+        let mut tx = ArmTransaction::create(vec![action], Delta::Witness(delta_witness));
+        tx.generate_delta_proof();
+        Ok((tx, transferred))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification::{self, ExpectedImageIds};
+
+    #[test]
+    fn transitions_apply_to_the_value() {
+        assert_eq!(CounterTransition::Increment.apply(41), Ok(42));
+        assert_eq!(CounterTransition::Decrement.apply(42), Ok(41));
+        assert_eq!(CounterTransition::Add(5).apply(37), Ok(42));
+        assert_eq!(CounterTransition::Reset.apply(42), Ok(0));
+        assert!(CounterTransition::Decrement.apply(0).is_err());
+        assert!(CounterTransition::Add(1).apply(u128::MAX).is_err());
+    }
+
+    // The guest runs in dev mode as well, so only what it constrains may be proved
+    #[test]
+    fn only_increments_are_proved() {
+        assert!(check_guest_support("increment").is_ok());
+        for operation in ["decrement", "add", "reset", "close", "transfer"] {
+            assert!(check_guest_support(operation).is_err(), "{} was not rejected", operation);
+        }
+    }
+
+    #[test]
+    fn add_requires_a_signed_amount() {
+        let intent = CounterIntent::new("add", Some(5)).unwrap();
+        assert!(intent.verify_message("Action: ADD\nAmount: 5").is_ok());
+        assert!(intent.verify_message("Action: ADD\nAmount: 50").is_err());
+        assert!(CounterIntent::new("add", None).is_err());
+        assert!(CounterIntent::new("reset", Some(1)).is_err());
+    }
+
+    // Initialization and increment are proved and verified locally in dev mode,
+    // without a chain, so the default Merkle path is used
+    #[test]
+    fn init_and_increment_verify() {
+        verification::use_dev_mode();
+        let (init_tx, counter, nf_key) = app::init::create_init_counter_tx();
+        assert_eq!(verification::verify_arm_proofs(&init_tx, &ExpectedImageIds::counter()).into_result(), Ok(()));
+        assert_eq!(counter_value(&counter), 0);
+
+        let (tx, next) =
+            create_transition_tx_with_merkle_path(counter.clone(), nf_key.clone(), MerklePath::default(), CounterTransition::Increment)
+                .unwrap();
+        assert_eq!(verification::verify_arm_proofs(&tx, &ExpectedImageIds::counter()).into_result(), Ok(()));
+        assert_eq!(counter_value(&next), 1);
+        assert_eq!(next.nonce, counter.nullifier(&nf_key).unwrap().as_bytes());
+    }
+
+    #[test]
+    fn other_transitions_are_refused_before_proving() {
+        verification::use_dev_mode();
+        let (_, counter, nf_key) = app::init::create_init_counter_tx();
+        for transition in [CounterTransition::Add(41), CounterTransition::Reset] {
+            let result =
+                create_transition_tx_with_merkle_path(counter.clone(), nf_key.clone(), MerklePath::default(), transition);
+            assert!(result.is_err(), "{:?} was proved", transition);
+        }
+        assert!(create_close_tx_with_merkle_path(counter, nf_key, MerklePath::default()).is_err());
+    }
+
+    // The created resources of every transition, as a counter guest will see them
+    #[test]
+    fn transition_resources_chain_from_the_consumed_counter() {
+        verification::use_dev_mode();
+        let (_, mut counter, nf_key) = app::init::create_init_counter_tx();
+        let steps = [
+            (CounterTransition::Increment, 1),
+            (CounterTransition::Add(41), 42),
+            (CounterTransition::Decrement, 41),
+            (CounterTransition::Reset, 0),
+        ];
+        for (transition, expected) in steps {
+            let next = transition_counter(&counter, &nf_key, transition).unwrap();
+            assert_eq!(counter_value(&next), expected, "{:?}", transition);
+            assert_eq!(next.nonce, counter.nullifier(&nf_key).unwrap().as_bytes(), "{:?}", transition);
+            counter = next;
+        }
+        assert!(transition_counter(&counter, &nf_key, CounterTransition::Decrement).is_err());
+
+        let ephemeral = closing_resource(&counter, &nf_key).unwrap();
+        assert!(ephemeral.is_ephemeral);
+        assert_eq!(counter_value(&ephemeral), 0);
+    }
+}
@@ -0,0 +1,266 @@
+// On-chain consistency check of a counter's history.
+//
+// Each history entry claims that its transaction consumed the previous counter
+// resource (published its nullifier) and created the next one (added its
+// commitment). The check walks the history and confirms, against the Protocol
+// Adapter, that the chain of nullifiers and commitments from initialization up to
+// the current resource (or its closing transaction) is unbroken.
+
+use std::collections::HashSet;
+
+use alloy::consensus::Transaction as _;
+use alloy::network::ReceiptResponse;
+use alloy::primitives::{hex, B256};
+use alloy::providers::Provider;
+use alloy::sol_types::SolCall;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::Serialize;
+
+use crate::counter_store::{CounterRecord, HistoryEntry};
+use crate::verification::digest_to_b256;
+
+#[derive(Serialize)]
+pub struct AuditReport {
+    pub counter_id: String,
+    pub entries_checked: usize,
+    pub consistent: bool,
+    pub errors: Vec<String>,
+}
+
+// Nullifiers and commitments of an executed Protocol Adapter transaction.
+pub struct ExecutedTags {
+    pub block_number: Option<u64>,
+    pub nullifiers: HashSet<String>,
+    pub commitments: HashSet<String>,
+}
+
+fn tag_hex(tag: &B256) -> String {
+    format!("0x{}", hex::encode(tag))
+}
+
+// A successfully executed Protocol Adapter transaction, decoded from its calldata.
+pub struct ExecutedCall {
+    pub block_number: Option<u64>,
+    pub call: ProtocolAdapter::executeCall,
+}
+
+pub async fn executed_call(tx_hash: &str) -> Result<ExecutedCall, String> {
+    let hash: B256 = tx_hash
+        .parse()
+        .map_err(|e| format!("Invalid transaction hash {}: {}", tx_hash, e))?;
+    let adapter = protocol_adapter();
+    let provider = adapter.provider();
+
+    let receipt = provider
+        .get_transaction_receipt(hash)
+        .await
+        .map_err(|e| format!("Failed to get receipt of {}: {}", tx_hash, e))?
+        .ok_or_else(|| format!("Transaction {} is not mined", tx_hash))?;
+    if !receipt.status() {
+        return Err(format!("Transaction {} reverted", tx_hash));
+    }
+
+    let tx = provider
+        .get_transaction_by_hash(hash)
+        .await
+        .map_err(|e| format!("Failed to get transaction {}: {}", tx_hash, e))?
+        .ok_or_else(|| format!("Transaction {} not found", tx_hash))?;
+    let call = ProtocolAdapter::executeCall::abi_decode(tx.input())
+        .map_err(|e| format!("Transaction {} is not a Protocol Adapter execute call: {}", tx_hash, e))?;
+
+    Ok(ExecutedCall {
+        block_number: receipt.block_number(),
+        call,
+    })
+}
+
+// Collects the tags an executed Protocol Adapter transaction consumed and created.
+pub async fn executed_tags(tx_hash: &str) -> Result<ExecutedTags, String> {
+    let executed = executed_call(tx_hash).await?;
+    let mut tags = ExecutedTags {
+        block_number: executed.block_number,
+        nullifiers: HashSet::new(),
+        commitments: HashSet::new(),
+    };
+    for action in &executed.call.transaction.actions {
+        for unit in &action.complianceVerifierInputs {
+            tags.nullifiers.insert(tag_hex(&unit.instance.consumed.nullifier));
+            tags.commitments.insert(tag_hex(&unit.instance.created.commitment));
+        }
+    }
+    Ok(tags)
+}
+
+// Block numbers of the given entries' transactions that are not recorded yet.
+pub async fn resolve_block_numbers(entries: &[HistoryEntry]) -> Vec<(String, u64)> {
+    let adapter = protocol_adapter();
+    let mut resolved = vec![];
+    for entry in entries.iter().filter(|entry| entry.block_number.is_none()) {
+        let Some(tx_hash) = &entry.tx_hash else { continue };
+        let Ok(hash) = tx_hash.parse::<B256>() else { continue };
+        if let Ok(Some(receipt)) = adapter.provider().get_transaction_receipt(hash).await {
+            if let Some(block_number) = receipt.block_number() {
+                resolved.push((tx_hash.clone(), block_number));
+            }
+        }
+    }
+    resolved
+}
+
+pub async fn is_nullifier_published(nullifier: &str) -> Result<bool, String> {
+    let nullifier: B256 = nullifier
+        .parse()
+        .map_err(|e| format!("Invalid nullifier {}: {}", nullifier, e))?;
+    protocol_adapter()
+        .contains(nullifier)
+        .call()
+        .await
+        .map_err(|e| format!("Failed to call contains: {}", e))
+}
+
+// The history starts with an initialization, every entry consumes the resource
+// created by the previous one, and the last entry created the current resource.
+fn check_links(record: &CounterRecord) -> Vec<String> {
+    let mut errors = vec![];
+    let history = &record.history;
+    match history.first() {
+        Some(first) if first.transition == "initialize" && first.previous_commitment.is_none() => {}
+        Some(_) => errors.push("History does not start with an initialization".to_string()),
+        None => errors.push("History is empty".to_string()),
+    }
+    for (i, pair) in history.windows(2).enumerate() {
+        if pair[1].previous_commitment.as_deref() != Some(pair[0].new_commitment.as_str()) {
+            errors.push(format!(
+                "Entry {} consumes {:?}, but entry {} created {}",
+                i + 1,
+                pair[1].previous_commitment,
+                i,
+                pair[0].new_commitment
+            ));
+        }
+    }
+    let current = format!("0x{}", hex::encode(record.resource.commitment().as_bytes()));
+    if history.last().map(|entry| &entry.new_commitment) != Some(&current) {
+        errors.push(format!("Last history entry does not create the current resource {}", current));
+    }
+    errors
+}
+
+// Checks that the history links up locally, that every transaction in it consumed
+// and created the recorded tags, and that the current resource is still unspent.
+pub async fn check_counter_history(record: &CounterRecord) -> AuditReport {
+    let mut errors = vec![];
+    let history = &record.history;
+
+    // Step 1: The history links up locally
+    errors.extend(check_links(record));
+
+    // Step 2: Every transaction was executed and published the recorded tags
+    for (i, entry) in history.iter().enumerate() {
+        let Some(tx_hash) = &entry.tx_hash else {
+            errors.push(format!("Entry {} has no transaction", i));
+            continue;
+        };
+        let tags = match executed_tags(tx_hash).await {
+            Ok(tags) => tags,
+            Err(e) => {
+                errors.push(format!("Entry {}: {}", i, e));
+                continue;
+            }
+        };
+        if let (Some(recorded), Some(actual)) = (entry.block_number, tags.block_number) {
+            if recorded != actual {
+                errors.push(format!("Entry {}: recorded block {}, mined in {}", i, recorded, actual));
+            }
+        }
+        if !tags.commitments.contains(&entry.new_commitment) {
+            errors.push(format!("Entry {}: {} did not create {}", i, tx_hash, entry.new_commitment));
+        }
+        if let Some(nullifier) = &entry.nullifier {
+            if !tags.nullifiers.contains(nullifier) {
+                errors.push(format!("Entry {}: {} did not consume nullifier {}", i, tx_hash, nullifier));
+            }
+            match is_nullifier_published(nullifier).await {
+                Ok(true) => {}
+                Ok(false) => errors.push(format!("Entry {}: nullifier {} is not in the nullifier set", i, nullifier)),
+                Err(e) => errors.push(format!("Entry {}: {}", i, e)),
+            }
+        }
+    }
+
+    // Step 3: Nobody consumed the current resource outside of this history. A closed
+    // or transferred counter's final resource is consumed by its last entry, checked
+    // above.
+    match (&record.nf_key, record.closed) {
+        (_, true) => {}
+        (Some(nf_key), false) => match record.resource.nullifier(nf_key) {
+            Some(nullifier) => match protocol_adapter().contains(digest_to_b256(&nullifier)).call().await {
+                Ok(false) => {}
+                Ok(true) => errors.push("Current resource has already been consumed".to_string()),
+                Err(e) => errors.push(format!("Failed to call contains: {}", e)),
+            },
+            None => errors.push("Nullifier key does not match the current resource".to_string()),
+        },
+        // Restored from disk, where nullifier keys are not kept, so the current
+        // nullifier cannot be derived
+        (None, false) => {}
+    }
+
+    AuditReport {
+        counter_id: record.counter_id.clone(),
+        entries_checked: history.len(),
+        consistent: errors.is_empty(),
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::{self, CounterTransition};
+    use crate::counter_store::CounterStore;
+    use crate::ownership::Ownership;
+
+    const ALICE: &str = "0x00000000000000000000000000000000000000a1";
+
+    fn record_with_two_entries() -> CounterRecord {
+        crate::verification::use_dev_mode();
+        let mut store = CounterStore::default();
+        let (_, first, nf_key) = app::init::create_init_counter_tx();
+        let counter_id = store.insert_new(ALICE, Ownership::single(ALICE), first.clone(), nf_key.clone(), None);
+        let second = counter::transition_counter(&first, &nf_key, CounterTransition::Increment).unwrap();
+        store.advance(&counter_id, &first, second, "increment", ALICE, None).unwrap();
+        store.get(&counter_id).unwrap().clone()
+    }
+
+    #[test]
+    fn linked_history_passes_the_local_checks() {
+        assert!(check_links(&record_with_two_entries()).is_empty());
+    }
+
+    #[test]
+    fn broken_link_is_reported() {
+        let mut record = record_with_two_entries();
+        record.history[1].previous_commitment = Some(format!("0x{}", "00".repeat(32)));
+        let errors = check_links(&record);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Entry 1 consumes"));
+    }
+
+    #[test]
+    fn history_must_start_with_an_initialization() {
+        let mut record = record_with_two_entries();
+        record.history.remove(0);
+        assert!(check_links(&record).contains(&"History does not start with an initialization".to_string()));
+    }
+
+    #[test]
+    fn history_must_end_at_the_current_resource() {
+        let mut record = record_with_two_entries();
+        record.history.pop();
+        assert!(check_links(&record)
+            .iter()
+            .any(|error| error.starts_with("Last history entry does not create the current resource")));
+    }
+}
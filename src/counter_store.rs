@@ -0,0 +1,460 @@
+// Store of counters and the nullifier keys needed to consume them.
+//
+// Counters are identified by the commitment of the counter resource created at
+// initialization; the id stays the same while the counter's current resource
+// moves forward with every transition. Each account acts on its most recently
+// initialized counter by default; shared counters are addressed by id.
+//
+// Every record keeps an append-only history with one entry per transaction that
+// created one of its resources, so past values and transitions can be audited.
+// Closed counters are kept as tombstones: the history stays, the nullifier key is
+// dropped and the counter can no longer be advanced.
+//
+// Each record is written to COUNTER_STORE_DIR (default `counters/`) as
+// `<counter id>.json` whenever it changes, so histories survive a restart. Nullifier
+// keys are kept in memory only; a counter restored from disk can be read and audited
+// but not advanced. Accounts are matched case-insensitively, as owners are.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use alloy::primitives::hex;
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use serde::{Deserialize, Serialize};
+
+use crate::counter;
+use crate::ownership::Ownership;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    // `initialize` or the name of the counter transition
+    pub transition: String,
+    // Commitment and nullifier of the consumed resource; None for initialization
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_commitment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullifier: Option<String>,
+    pub new_commitment: String,
+    pub value: u128,
+    pub tx_hash: Option<String>,
+    // Filled in once the transaction receipt has been looked up
+    pub block_number: Option<u64>,
+    // Unix time at which the transaction was submitted
+    pub timestamp: u64,
+    pub signer: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CounterRecord {
+    pub counter_id: String,
+    // Account that initialized the counter
+    pub owner: String,
+    pub ownership: Ownership,
+    // For closed counters, the ephemeral resource created by the closing transaction;
+    // for transferred ones, the resource created for the recipient
+    pub resource: Resource,
+    // None once the counter is closed, and for counters restored from disk
+    #[serde(skip)]
+    pub nf_key: Option<NullifierKey>,
+    // Transaction that created the current resource, if it was submitted by us. For
+    // closed counters this is the closing or transferring transaction.
+    pub created_in: Option<String>,
+    // Closed or transferred away
+    pub closed: bool,
+    pub history: Vec<HistoryEntry>,
+}
+
+#[derive(Default)]
+pub struct CounterStore {
+    counters: HashMap<String, CounterRecord>,
+    // Lowercased account -> id of its active counter
+    active: HashMap<String, String>,
+    // Where records are persisted; None keeps them in memory only
+    dir: Option<PathBuf>,
+}
+
+// One lock per counter. An operation holds it from reading the counter's current
+// resource until the store records the resource it created, so two owners acting
+// on a shared counter at once do not both consume the same resource.
+#[derive(Clone, Default)]
+pub struct CounterLocks(Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>);
+
+impl CounterLocks {
+    pub async fn lock(&self, counter_id: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = self.0.lock().unwrap().entry(counter_id.to_string()).or_default().clone();
+        lock.lock_owned().await
+    }
+}
+
+fn account_key(account: &str) -> String {
+    account.to_lowercase()
+}
+
+fn commitment_hex(resource: &Resource) -> String {
+    format!("0x{}", hex::encode(resource.commitment().as_bytes()))
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl CounterStore {
+    // Opens the store persisted in `dir`. Each owner's active counter is the open
+    // counter it most recently initialized.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create counter store directory {}: {}", dir.display(), e))?;
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read counter store directory {}: {}", dir.display(), e))?;
+
+        let mut records: Vec<CounterRecord> = vec![];
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let json = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let record = serde_json::from_slice(&json)
+                .map_err(|e| format!("Corrupt counter record {}: {}", path.display(), e))?;
+            records.push(record);
+        }
+        records.sort_by_key(|record| record.history.first().map_or(0, |entry| entry.timestamp));
+
+        let mut store = CounterStore {
+            dir: Some(dir),
+            ..Default::default()
+        };
+        for record in records {
+            if !record.closed {
+                store.active.insert(account_key(&record.owner), record.counter_id.clone());
+            }
+            store.counters.insert(record.counter_id.clone(), record);
+        }
+        println!("Restored {} counters from the counter store", store.counters.len());
+        Ok(store)
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        Self::open(std::env::var("COUNTER_STORE_DIR").unwrap_or_else(|_| "counters".to_string()))
+    }
+
+    // Writes a changed record to disk. The in-memory state stays authoritative, so a
+    // failed write is logged rather than undoing the change.
+    fn persist(&self, counter_id: &str) {
+        let (Some(dir), Some(record)) = (&self.dir, self.counters.get(counter_id)) else {
+            return;
+        };
+        let path = dir.join(format!("{}.json", counter_id));
+        let result = serde_json::to_vec_pretty(record)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            println!("Failed to persist counter {} to {}: {}", counter_id, path.display(), e);
+        }
+    }
+
+    // Records a newly initialized counter and makes it the account's active counter.
+    pub fn insert_new(
+        &mut self,
+        owner: &str,
+        ownership: Ownership,
+        resource: Resource,
+        nf_key: NullifierKey,
+        created_in: Option<String>,
+    ) -> String {
+        let counter_id = commitment_hex(&resource);
+        let entry = HistoryEntry {
+            transition: "initialize".to_string(),
+            previous_commitment: None,
+            nullifier: None,
+            new_commitment: counter_id.clone(),
+            value: counter::counter_value(&resource),
+            tx_hash: created_in.clone(),
+            block_number: None,
+            timestamp: now(),
+            signer: owner.to_string(),
+        };
+        self.counters.insert(
+            counter_id.clone(),
+            CounterRecord {
+                counter_id: counter_id.clone(),
+                owner: owner.to_string(),
+                ownership,
+                resource,
+                nf_key: Some(nf_key),
+                created_in,
+                closed: false,
+                history: vec![entry],
+            },
+        );
+        self.active.insert(account_key(owner), counter_id.clone());
+        self.persist(&counter_id);
+        counter_id
+    }
+
+    pub fn get(&self, counter_id: &str) -> Option<&CounterRecord> {
+        self.counters.get(counter_id)
+    }
+
+    pub fn active_for(&self, account: &str) -> Option<&CounterRecord> {
+        self.active.get(&account_key(account)).and_then(|id| self.counters.get(id))
+    }
+
+    // The account's active counter with what is needed to consume it.
+    pub fn active_counter(&self, account: &str) -> Option<(String, Resource, NullifierKey)> {
+        let record = self.active_for(account)?;
+        let nf_key = record.nf_key.clone()?;
+        Some((record.counter_id.clone(), record.resource.clone(), nf_key))
+    }
+
+    // The open counter an account acts on: the given one if the account owns it,
+    // otherwise the account's active counter.
+    pub fn select(&self, account: &str, counter_id: Option<&str>) -> Result<&CounterRecord, String> {
+        let record = match counter_id {
+            Some(counter_id) => self
+                .counters
+                .get(counter_id)
+                .filter(|record| record.ownership.is_owner(account))
+                .ok_or_else(|| format!("No counter {} owned by {}", counter_id, account))?,
+            None => self.active_for(account).ok_or_else(|| {
+                "Counter not initialized for this user. Please run the counter initialization transaction first."
+                    .to_string()
+            })?,
+        };
+        if record.closed {
+            return Err(format!("Counter {} is closed", record.counter_id));
+        }
+        Ok(record)
+    }
+
+    // Counters the account owns or co-owns.
+    pub fn for_account(&self, account: &str) -> Vec<&CounterRecord> {
+        let mut records: Vec<&CounterRecord> = self
+            .counters
+            .values()
+            .filter(|record| record.ownership.is_owner(account))
+            .collect();
+        records.sort_by(|a, b| a.counter_id.cmp(&b.counter_id));
+        records
+    }
+
+    // The open counter whose current resource has the given hex commitment.
+    pub fn find_open(&self, commitment: &str) -> Option<&CounterRecord> {
+        self.counters
+            .values()
+            .find(|record| !record.closed && commitment_hex(&record.resource).eq_ignore_ascii_case(commitment))
+    }
+
+    // The open counter whose current resource has the given commitment.
+    pub fn find_by_commitment(&self, resource: &Resource) -> Option<&CounterRecord> {
+        let commitment = resource.commitment();
+        self.counters
+            .values()
+            .find(|record| !record.closed && record.resource.commitment() == commitment)
+    }
+
+    // Moves a counter forward to the resource created by `created_in` and appends the
+    // transition to its history. `consumed` is the resource the transaction consumed;
+    // if the counter has moved on since, nothing is recorded.
+    pub fn advance(
+        &mut self,
+        counter_id: &str,
+        consumed: &Resource,
+        resource: Resource,
+        transition: &str,
+        signer: &str,
+        created_in: Option<String>,
+    ) -> Result<(), String> {
+        let record = self.open_record(counter_id)?;
+        if record.resource.commitment() != consumed.commitment() {
+            return Err(format!(
+                "Counter {} is at {}, not at the consumed {}",
+                counter_id,
+                commitment_hex(&record.resource),
+                commitment_hex(consumed)
+            ));
+        }
+        Self::record_transition(record, &resource, transition, signer, created_in.clone())?;
+        record.resource = resource;
+        record.created_in = created_in;
+        self.persist(counter_id);
+        Ok(())
+    }
+
+    // Tombstones a counter whose final resource was consumed by `closed_in`: the
+    // closing transition is appended to the history, the nullifier key is deleted and
+    // the counter stops being any owner's active counter.
+    pub fn close(
+        &mut self,
+        counter_id: &str,
+        ephemeral: Resource,
+        signer: &str,
+        closed_in: Option<String>,
+    ) -> Result<(), String> {
+        self.retire(counter_id, ephemeral, "close", signer, closed_in)
+    }
+
+    // Tombstones a counter handed to another user by `transferred_in`. The recipient's
+    // resource is recorded as the final one; its nullifier key is the recipient's, so
+    // the counter can no longer be advanced from this store.
+    pub fn transfer(
+        &mut self,
+        counter_id: &str,
+        sent: Resource,
+        signer: &str,
+        transferred_in: Option<String>,
+    ) -> Result<(), String> {
+        self.retire(counter_id, sent, "transfer", signer, transferred_in)
+    }
+
+    fn retire(
+        &mut self,
+        counter_id: &str,
+        resource: Resource,
+        transition: &str,
+        signer: &str,
+        retired_in: Option<String>,
+    ) -> Result<(), String> {
+        let record = self.open_record(counter_id)?;
+        Self::record_transition(record, &resource, transition, signer, retired_in.clone())?;
+        record.resource = resource;
+        record.created_in = retired_in;
+        record.nf_key = None;
+        record.closed = true;
+
+        self.active.retain(|_, active_id| active_id != counter_id);
+        self.persist(counter_id);
+        Ok(())
+    }
+
+    fn open_record(&mut self, counter_id: &str) -> Result<&mut CounterRecord, String> {
+        let record = self
+            .counters
+            .get_mut(counter_id)
+            .ok_or_else(|| format!("Unknown counter {}", counter_id))?;
+        if record.closed {
+            return Err(format!("Counter {} is closed", counter_id));
+        }
+        Ok(record)
+    }
+
+    fn record_transition(
+        record: &mut CounterRecord,
+        resource: &Resource,
+        transition: &str,
+        signer: &str,
+        created_in: Option<String>,
+    ) -> Result<(), String> {
+        let nf_key = record.nf_key.as_ref().ok_or_else(|| "Counter has no nullifier key".to_string())?;
+        let nullifier = record
+            .resource
+            .nullifier(nf_key)
+            .ok_or_else(|| "Nullifier key does not match the counter resource".to_string())?;
+
+        record.history.push(HistoryEntry {
+            transition: transition.to_string(),
+            previous_commitment: Some(commitment_hex(&record.resource)),
+            nullifier: Some(format!("0x{}", hex::encode(nullifier.as_bytes()))),
+            new_commitment: commitment_hex(resource),
+            value: counter::counter_value(resource),
+            tx_hash: created_in,
+            block_number: None,
+            timestamp: now(),
+            signer: signer.to_string(),
+        });
+        Ok(())
+    }
+
+    // Records the block a transaction of the counter's history was included in.
+    pub fn set_block_number(&mut self, counter_id: &str, tx_hash: &str, block_number: u64) {
+        if let Some(record) = self.counters.get_mut(counter_id) {
+            for entry in record.history.iter_mut() {
+                if entry.tx_hash.as_deref() == Some(tx_hash) {
+                    entry.block_number = Some(block_number);
+                }
+            }
+            self.persist(counter_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::CounterTransition;
+
+    const ALICE: &str = "0xAbCd000000000000000000000000000000000001";
+
+    fn initialized(store: &mut CounterStore) -> (String, Resource, NullifierKey) {
+        crate::verification::use_dev_mode();
+        let (_, resource, nf_key) = app::init::create_init_counter_tx();
+        let counter_id = store.insert_new(ALICE, Ownership::single(ALICE), resource.clone(), nf_key.clone(), Some("0x01".to_string()));
+        (counter_id, resource, nf_key)
+    }
+
+    fn increment(resource: &Resource, nf_key: &NullifierKey) -> Resource {
+        counter::transition_counter(resource, nf_key, CounterTransition::Increment).unwrap()
+    }
+
+    #[test]
+    fn history_links_every_transition_to_the_previous_resource() {
+        let mut store = CounterStore::default();
+        let (counter_id, first, nf_key) = initialized(&mut store);
+        let second = increment(&first, &nf_key);
+        store.advance(&counter_id, &first, second.clone(), "increment", ALICE, Some("0x02".to_string())).unwrap();
+
+        let history = &store.get(&counter_id).unwrap().history;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].previous_commitment.as_deref(), Some(history[0].new_commitment.as_str()));
+        assert_eq!(history[1].new_commitment, commitment_hex(&second));
+        assert_eq!(history[1].value, 1);
+        assert_eq!(
+            history[1].nullifier,
+            Some(format!("0x{}", hex::encode(first.nullifier(&nf_key).unwrap().as_bytes())))
+        );
+    }
+
+    #[test]
+    fn advance_from_a_stale_resource_is_rejected() {
+        let mut store = CounterStore::default();
+        let (counter_id, first, nf_key) = initialized(&mut store);
+        let second = increment(&first, &nf_key);
+        store.advance(&counter_id, &first, second.clone(), "increment", ALICE, None).unwrap();
+
+        // A second transaction that consumed the same resource must not move the counter
+        let duplicate = increment(&first, &nf_key);
+        assert!(store.advance(&counter_id, &first, duplicate, "increment", ALICE, None).is_err());
+        let record = store.get(&counter_id).unwrap();
+        assert_eq!(record.resource.commitment(), second.commitment());
+        assert_eq!(record.history.len(), 2);
+    }
+
+    #[test]
+    fn accounts_are_matched_case_insensitively() {
+        let mut store = CounterStore::default();
+        let (counter_id, _, _) = initialized(&mut store);
+        assert_eq!(store.active_counter(&ALICE.to_lowercase()).map(|(id, _, _)| id), Some(counter_id.clone()));
+        assert_eq!(store.active_counter(&ALICE.to_uppercase()).map(|(id, _, _)| id), Some(counter_id));
+    }
+
+    #[test]
+    fn history_survives_a_restart() {
+        let dir = std::env::temp_dir().join(format!("counter-store-{}", rand::random::<u64>()));
+        let mut store = CounterStore::open(&dir).unwrap();
+        let (counter_id, first, nf_key) = initialized(&mut store);
+        store.advance(&counter_id, &first, increment(&first, &nf_key), "increment", ALICE, None).unwrap();
+        let history: Vec<String> = store.get(&counter_id).unwrap().history.iter().map(|e| e.new_commitment.clone()).collect();
+
+        let restored = CounterStore::open(&dir).unwrap();
+        let record = restored.get(&counter_id).unwrap();
+        assert_eq!(record.history.iter().map(|e| e.new_commitment.clone()).collect::<Vec<_>>(), history);
+        assert!(record.nf_key.is_none());
+        assert_eq!(restored.active_for(ALICE).map(|record| record.counter_id.clone()), Some(counter_id));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,330 @@
+// Discovery of resources sent to a user.
+//
+// Every transfer publishes a discovery payload for its transaction:
+//
+//   discovery_ciphertext  DISCOVERY_MARKER || commitment, encrypted to the
+//                         recipient's static discovery key (sdpk)
+//   resource_ciphertext   the resource, encrypted to the recipient's sepk
+//
+// Both use the scheme in `encryption`. The logic instance's ciphertext field is
+// committed by the app's logic guest, and no guest in arm-risc0 v0.3.0 takes it as
+// input, so until one does payloads are stored off-chain, in the payload directory
+// of the backend that proved the transfer, rather than in the transaction itself.
+// Only clients of that backend can discover them; the chain only confirms that the
+// announced commitment was created.
+//
+// `published` walks the Protocol Adapter's TransactionExecuted events and returns
+// the payloads of every executed transaction with the commitments it created. The
+// API serves only these; secret keys never reach the server, and the frontend
+// trial-decrypts the discovery ciphertexts itself. `scan` does the same trial
+// decryption locally with the user's discovery secret key (sdsk) for the CLI.
+// Decryption only succeeds for payloads addressed to that user. Matches are then
+// decrypted with the encryption secret key (sesk), when given, and checked against
+// the commitments the transaction created.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use alloy::primitives::hex;
+use alloy::providers::Provider;
+use alloy::rpc::types::Filter;
+use alloy::sol_types::SolEvent;
+use arm_risc0::resource::Resource;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+
+use crate::counter_audit;
+use crate::encryption;
+use crate::registry::Registry;
+use crate::resource_store::commitment_hex;
+
+const DISCOVERY_MARKER: &[u8] = b"anoma-discovery:v1";
+
+// All fields are hex.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiscoveryPayload {
+    pub discovery_ciphertext: String,
+    pub resource_ciphertext: String,
+}
+
+impl DiscoveryPayload {
+    pub fn new(recipient_sdpk: &[u8], recipient_sepk: &[u8], resource: &Resource) -> Result<Self, String> {
+        let mut discovery = DISCOVERY_MARKER.to_vec();
+        discovery.extend_from_slice(resource.commitment().as_bytes());
+        Ok(DiscoveryPayload {
+            discovery_ciphertext: format!("0x{}", hex::encode(encryption::encrypt(recipient_sdpk, &discovery)?)),
+            resource_ciphertext: format!(
+                "0x{}",
+                hex::encode(encryption::encrypt_resource(recipient_sepk, resource)?)
+            ),
+        })
+    }
+
+    // The commitment announced to the owner of `sdsk`, or None if the payload is
+    // addressed to someone else.
+    fn trial_decrypt(&self, sdsk: &[u8]) -> Option<String> {
+        let ciphertext = hex::decode(&self.discovery_ciphertext).ok()?;
+        let plaintext = encryption::decrypt(sdsk, &ciphertext).ok()?;
+        let commitment = plaintext.strip_prefix(DISCOVERY_MARKER)?;
+        Some(format!("0x{}", hex::encode(commitment)))
+    }
+}
+
+// Discovery payloads on disk, one JSON file per transaction.
+pub struct PayloadStore {
+    dir: PathBuf,
+}
+
+impl PayloadStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create payload directory {}: {}", dir.display(), e))?;
+        Ok(PayloadStore { dir })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        Self::new(std::env::var("PAYLOAD_DIR").unwrap_or_else(|_| "payloads".to_string()))
+    }
+
+    fn path(&self, tx_hash: &str) -> Result<PathBuf, String> {
+        let valid = tx_hash.len() == 66
+            && tx_hash.starts_with("0x")
+            && tx_hash[2..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(format!("Invalid transaction hash: {}", tx_hash));
+        }
+        Ok(self.dir.join(format!("{}.json", tx_hash.to_lowercase())))
+    }
+
+    pub fn load(&self, tx_hash: &str) -> Result<Vec<DiscoveryPayload>, String> {
+        let path = self.path(tx_hash)?;
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let contents =
+            std::fs::read_to_string(&path).map_err(|e| format!("Failed to read payloads of {}: {}", tx_hash, e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid payload file for {}: {}", tx_hash, e))
+    }
+
+    pub fn publish(&self, tx_hash: &str, payload: DiscoveryPayload) -> Result<(), String> {
+        let mut payloads = self.load(tx_hash)?;
+        payloads.push(payload);
+        let contents = serde_json::to_string_pretty(&payloads)
+            .map_err(|e| format!("Failed to serialize payloads: {}", e))?;
+        std::fs::write(self.path(tx_hash)?, contents)
+            .map_err(|e| format!("Failed to write payloads of {}: {}", tx_hash, e))
+    }
+}
+
+#[derive(Serialize)]
+pub struct InboxEntry {
+    pub tx_hash: String,
+    pub block_number: Option<u64>,
+    pub commitment: String,
+    // Whether the transaction created the announced commitment
+    pub verified_on_chain: bool,
+    // Set when the resource was decrypted with the encryption secret key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<Resource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    // Returned when no encryption secret key was given, for decryption by the client
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_ciphertext: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Decrypts the resource of a discovered payload and describes it with the app it
+// belongs to.
+fn open_entry(entry: &mut InboxEntry, payload: &DiscoveryPayload, sesk: &[u8], registry: &Registry) {
+    let resource = hex::decode(&payload.resource_ciphertext)
+        .map_err(|e| format!("Resource ciphertext is not hex: {}", e))
+        .and_then(|ciphertext| encryption::decrypt_resource(sesk, &ciphertext));
+    let resource = match resource {
+        Ok(resource) => resource,
+        Err(e) => {
+            entry.error = Some(e);
+            return;
+        }
+    };
+    if commitment_hex(&resource) != entry.commitment {
+        entry.error = Some("Decrypted resource does not match the announced commitment".to_string());
+        return;
+    }
+
+    let logic_ref: &[u8] = resource.logic_ref.as_ref();
+    if let Some(app) = registry
+        .list()
+        .into_iter()
+        .find(|app| app.logic_ref().as_bytes() == logic_ref)
+    {
+        entry.app = Some(app.name().to_string());
+        entry.data = app.decode(&resource).ok();
+    }
+    entry.resource = Some(resource);
+}
+
+// A discovery payload of an executed transaction, for trial decryption by the
+// client. `created_commitments` lets it check the announced commitment.
+#[derive(Serialize)]
+pub struct PublishedPayload {
+    pub tx_hash: String,
+    pub block_number: Option<u64>,
+    pub created_commitments: Vec<String>,
+    #[serde(flatten)]
+    pub payload: DiscoveryPayload,
+}
+
+// Discovery payloads of the transactions executed from `from_block` on.
+pub async fn published(payloads: &PayloadStore, from_block: u64) -> Result<Vec<PublishedPayload>, String> {
+    let adapter = protocol_adapter();
+
+    // Step 1: Collect the executed transactions from the Protocol Adapter's events
+    let filter = Filter::new()
+        .address(*adapter.address())
+        .event_signature(ProtocolAdapter::TransactionExecuted::SIGNATURE_HASH)
+        .from_block(from_block);
+    let logs = adapter
+        .provider()
+        .get_logs(&filter)
+        .await
+        .map_err(|e| format!("Failed to get TransactionExecuted events: {}", e))?;
+
+    let mut seen = HashSet::new();
+    let mut listed = vec![];
+    for log in logs {
+        let Some(tx_hash) = log.transaction_hash else { continue };
+        let tx_hash = format!("0x{}", hex::encode(tx_hash));
+        if !seen.insert(tx_hash.clone()) {
+            continue;
+        }
+        let tx_payloads = payloads.load(&tx_hash)?;
+        if tx_payloads.is_empty() {
+            continue;
+        }
+
+        // Step 2: Attach the commitments the transaction created
+        let created_commitments: Vec<String> = match counter_audit::executed_tags(&tx_hash).await {
+            Ok(tags) => tags.commitments.into_iter().collect(),
+            Err(e) => {
+                println!("Failed to check {}: {}", tx_hash, e);
+                vec![]
+            }
+        };
+        listed.extend(tx_payloads.into_iter().map(|payload| PublishedPayload {
+            tx_hash: tx_hash.clone(),
+            block_number: log.block_number,
+            created_commitments: created_commitments.clone(),
+            payload,
+        }));
+    }
+    Ok(listed)
+}
+
+// Scans transactions executed from `from_block` on for resources addressed to the
+// owner of `sdsk`. Resources are decrypted when `sesk` is given.
+pub async fn scan(
+    payloads: &PayloadStore,
+    registry: &Registry,
+    sdsk: &[u8],
+    sesk: Option<&[u8]>,
+    from_block: u64,
+) -> Result<Vec<InboxEntry>, String> {
+    let mut inbox = vec![];
+    for published in published(payloads, from_block).await? {
+        let Some(commitment) = published.payload.trial_decrypt(sdsk) else { continue };
+        println!("Discovered resource {} in {}", commitment, published.tx_hash);
+
+        let mut entry = InboxEntry {
+            tx_hash: published.tx_hash,
+            block_number: published.block_number,
+            verified_on_chain: published.created_commitments.contains(&commitment),
+            commitment,
+            resource: None,
+            app: None,
+            data: None,
+            resource_ciphertext: None,
+            error: None,
+        };
+        match sesk {
+            Some(sesk) => open_entry(&mut entry, &published.payload, sesk, registry),
+            None => entry.resource_ciphertext = Some(published.payload.resource_ciphertext),
+        }
+        inbox.push(entry);
+    }
+    Ok(inbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arm_risc0::nullifier_key::NullifierKey;
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+    use k256::SecretKey;
+    use risc0_zkvm::sha::Digest;
+
+    // (secret key, compressed public key)
+    fn key_pair() -> (Vec<u8>, Vec<u8>) {
+        let secret_key = SecretKey::random(&mut rand::rngs::OsRng);
+        let public_key = secret_key.public_key().to_encoded_point(true).as_bytes().to_vec();
+        (secret_key.to_bytes().to_vec(), public_key)
+    }
+
+    fn resource() -> Resource {
+        let (_, owner) = NullifierKey::random_pair();
+        Resource::create(Digest::default(), Digest::default(), 1, Digest::default(), false, Digest::default(), owner)
+    }
+
+    #[test]
+    fn recipient_discovers_their_payload() {
+        let (sdsk, sdpk) = key_pair();
+        let (sesk, sepk) = key_pair();
+        let resource = resource();
+        let payload = DiscoveryPayload::new(&sdpk, &sepk, &resource).unwrap();
+
+        assert_eq!(payload.trial_decrypt(&sdsk), Some(commitment_hex(&resource)));
+        let ciphertext = hex::decode(&payload.resource_ciphertext).unwrap();
+        let decrypted = encryption::decrypt_resource(&sesk, &ciphertext).unwrap();
+        assert_eq!(commitment_hex(&decrypted), commitment_hex(&resource));
+    }
+
+    #[test]
+    fn payload_for_someone_else_is_not_discovered() {
+        let (_, sdpk) = key_pair();
+        let (sesk, sepk) = key_pair();
+        let payload = DiscoveryPayload::new(&sdpk, &sepk, &resource()).unwrap();
+
+        let (other_sdsk, _) = key_pair();
+        assert_eq!(payload.trial_decrypt(&other_sdsk), None);
+        // The recipient's encryption key does not open the discovery ciphertext either
+        assert_eq!(payload.trial_decrypt(&sesk), None);
+    }
+
+    #[test]
+    fn stored_payloads_are_found_by_their_recipient_only() {
+        let dir = std::env::temp_dir().join(format!("payloads-{}", rand::random::<u64>()));
+        let store = PayloadStore::new(&dir).unwrap();
+        let tx_hash = format!("0x{}", "ab".repeat(32));
+
+        let (alice_sdsk, alice_sdpk) = key_pair();
+        let (bob_sdsk, bob_sdpk) = key_pair();
+        let (_, sepk) = key_pair();
+        let to_alice = resource();
+        store.publish(&tx_hash, DiscoveryPayload::new(&alice_sdpk, &sepk, &to_alice).unwrap()).unwrap();
+        store.publish(&tx_hash, DiscoveryPayload::new(&bob_sdpk, &sepk, &resource()).unwrap()).unwrap();
+
+        let payloads = store.load(&tx_hash).unwrap();
+        assert_eq!(payloads.len(), 2);
+        let found: Vec<String> = payloads.iter().filter_map(|payload| payload.trial_decrypt(&alice_sdsk)).collect();
+        assert_eq!(found, vec![commitment_hex(&to_alice)]);
+        assert_eq!(payloads.iter().filter_map(|payload| payload.trial_decrypt(&bob_sdsk)).count(), 1);
+
+        assert!(store.load("0x1234").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
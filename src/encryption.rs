@@ -0,0 +1,247 @@
+// Encryption of resource plaintexts to a recipient's static encryption key (sepk),
+// compatible with `diffieHellman`, `kdf`, `encrypt` and `decrypt` in
+// frontend/src/services/cryptography.ts:
+//
+//   shared  = x-coordinate of esk * sepk (secp256k1)
+//   key     = HMAC-SHA256(key = shared, message = epk)
+//   payload = AES-256-GCM(key, iv, plaintext), tag appended
+//
+// A fresh ephemeral key pair (esk, epk) and a random 12-byte IV are drawn for every
+// ciphertext, serialized as epk (33 bytes, compressed) || iv || payload.
+//
+// fixtures/encryption_vectors.json holds vectors generated independently of this
+// module and of the frontend; `cargo run -- encryption-vectors` checks them.
+
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use alloy::primitives::hex;
+use arm_risc0::resource::Resource;
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{AffinePoint, PublicKey, SecretKey};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::Sha256;
+
+const PUBLIC_KEY_LEN: usize = 33;
+const IV_LEN: usize = 12;
+
+pub const DEFAULT_VECTORS_PATH: &str = "fixtures/encryption_vectors.json";
+
+fn parse_public_key(bytes: &[u8]) -> Result<PublicKey, String> {
+    if bytes.len() != PUBLIC_KEY_LEN {
+        return Err(format!(
+            "Invalid public key length: {}, expected {}",
+            bytes.len(),
+            PUBLIC_KEY_LEN
+        ));
+    }
+    PublicKey::from_sec1_bytes(bytes).map_err(|_| "Invalid public key: not on curve".to_string())
+}
+
+pub fn validate_public_key(bytes: &[u8]) -> Result<(), String> {
+    parse_public_key(bytes).map(|_| ())
+}
+
+fn parse_secret_key(bytes: &[u8]) -> Result<SecretKey, String> {
+    SecretKey::from_slice(bytes).map_err(|_| "Invalid secret key".to_string())
+}
+
+fn compressed(public_key: &PublicKey) -> Vec<u8> {
+    public_key.to_encoded_point(true).as_bytes().to_vec()
+}
+
+// x-coordinate of the shared point, as returned by `diffieHellman`.
+fn diffie_hellman(secret_key: &SecretKey, public_key: &PublicKey) -> [u8; 32] {
+    let shared = AffinePoint::from(public_key.to_projective() * *secret_key.to_nonzero_scalar());
+    let point = shared.to_encoded_point(false);
+    let mut x = [0u8; 32];
+    x.copy_from_slice(point.x().expect("shared point is not the identity"));
+    x
+}
+
+fn kdf(shared_secret: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(shared_secret).expect("HMAC accepts any key length");
+    mac.update(info);
+    mac.finalize().into_bytes().into()
+}
+
+// Encryption with a given ephemeral key and IV. Only the test vectors pick them.
+fn encrypt_with(
+    ephemeral_key: &SecretKey,
+    iv: &[u8; IV_LEN],
+    recipient: &PublicKey,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    let epk = compressed(&ephemeral_key.public_key());
+    let key = kdf(&diffie_hellman(ephemeral_key, recipient), &epk);
+    let payload = Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Invalid AES key: {}", e))?
+        .encrypt(Nonce::from_slice(iv), plaintext)
+        .map_err(|_| "AES-GCM encryption failed".to_string())?;
+
+    let mut ciphertext = epk;
+    ciphertext.extend_from_slice(iv);
+    ciphertext.extend_from_slice(&payload);
+    Ok(ciphertext)
+}
+
+// Encrypts `plaintext` to the recipient's compressed sepk.
+pub fn encrypt(recipient_sepk: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let recipient = parse_public_key(recipient_sepk)?;
+    let ephemeral_key = SecretKey::random(&mut rand::rngs::OsRng);
+    let mut iv = [0u8; IV_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut iv);
+    encrypt_with(&ephemeral_key, &iv, &recipient, plaintext)
+}
+
+// Decrypts a ciphertext with the recipient's static encryption secret key (sesk).
+pub fn decrypt(recipient_sesk: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if ciphertext.len() < PUBLIC_KEY_LEN + IV_LEN {
+        return Err(format!("Ciphertext is too short: {} bytes", ciphertext.len()));
+    }
+    let (epk, rest) = ciphertext.split_at(PUBLIC_KEY_LEN);
+    let (iv, payload) = rest.split_at(IV_LEN);
+
+    let secret_key = parse_secret_key(recipient_sesk)?;
+    let key = kdf(&diffie_hellman(&secret_key, &parse_public_key(epk)?), epk);
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|e| format!("Invalid AES key: {}", e))?
+        .decrypt(Nonce::from_slice(iv), payload)
+        .map_err(|_| "Decryption failed: wrong key or corrupted ciphertext".to_string())
+}
+
+// The plaintext of a resource is its JSON encoding, so that the frontend can read it
+// back without a bincode decoder.
+pub fn encrypt_resource(recipient_sepk: &[u8], resource: &Resource) -> Result<Vec<u8>, String> {
+    let plaintext = serde_json::to_vec(resource).map_err(|e| format!("Failed to encode resource: {}", e))?;
+    encrypt(recipient_sepk, &plaintext)
+}
+
+pub fn decrypt_resource(recipient_sesk: &[u8], ciphertext: &[u8]) -> Result<Resource, String> {
+    let plaintext = decrypt(recipient_sesk, ciphertext)?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Decrypted data is not a resource: {}", e))
+}
+
+#[derive(Deserialize)]
+struct VectorFile {
+    vectors: Vec<Vector>,
+}
+
+// All fields are hex.
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    recipient_secret_key: String,
+    recipient_public_key: String,
+    ephemeral_secret_key: String,
+    ephemeral_public_key: String,
+    iv: String,
+    plaintext: String,
+    shared_secret: String,
+    key: String,
+    ciphertext: String,
+}
+
+fn check_vector(vector: &Vector) -> Result<(), String> {
+    let bytes = |field: &str, value: &str| hex::decode(value).map_err(|e| format!("{} is not hex: {}", field, e));
+
+    let recipient_key = parse_secret_key(&bytes("recipient_secret_key", &vector.recipient_secret_key)?)?;
+    let ephemeral_key = parse_secret_key(&bytes("ephemeral_secret_key", &vector.ephemeral_secret_key)?)?;
+    let recipient = recipient_key.public_key();
+    let iv: [u8; IV_LEN] = bytes("iv", &vector.iv)?
+        .try_into()
+        .map_err(|_| "iv must be 12 bytes".to_string())?;
+    let plaintext = bytes("plaintext", &vector.plaintext)?;
+    let ciphertext = bytes("ciphertext", &vector.ciphertext)?;
+
+    let check = |field: &str, expected: &str, actual: &[u8]| {
+        if hex::encode(actual) == expected.to_lowercase() {
+            Ok(())
+        } else {
+            Err(format!("{}: expected {}, got {}", field, expected, hex::encode(actual)))
+        }
+    };
+    check("recipient_public_key", &vector.recipient_public_key, &compressed(&recipient))?;
+    check(
+        "ephemeral_public_key",
+        &vector.ephemeral_public_key,
+        &compressed(&ephemeral_key.public_key()),
+    )?;
+    let shared_secret = diffie_hellman(&ephemeral_key, &recipient);
+    check("shared_secret", &vector.shared_secret, &shared_secret)?;
+    check("key", &vector.key, &kdf(&shared_secret, &compressed(&ephemeral_key.public_key())))?;
+    check(
+        "ciphertext",
+        &vector.ciphertext,
+        &encrypt_with(&ephemeral_key, &iv, &recipient, &plaintext)?,
+    )?;
+    check("decrypted", &vector.plaintext, &decrypt(&recipient_key.to_bytes(), &ciphertext)?)?;
+
+    // A fresh encryption must round-trip too
+    let fresh = encrypt(&compressed(&recipient), &plaintext)?;
+    check("round trip", &vector.plaintext, &decrypt(&recipient_key.to_bytes(), &fresh)?)
+}
+
+// Checks every vector in the file and returns the number of failures.
+pub fn run_vector_checks(path: &str) -> Result<usize, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let file: VectorFile = serde_json::from_str(&contents).map_err(|e| format!("Invalid vector file {}: {}", path, e))?;
+
+    let mut failures = 0;
+    for vector in &file.vectors {
+        match check_vector(vector) {
+            Ok(()) => println!("PASS {}", vector.name),
+            Err(e) => {
+                println!("FAIL {}: {}", vector.name, e);
+                failures += 1;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn independent_vectors_pass() {
+        let contents = std::fs::read_to_string(DEFAULT_VECTORS_PATH).unwrap();
+        let file: VectorFile = serde_json::from_str(&contents).unwrap();
+
+        assert!(!file.vectors.is_empty());
+        for vector in &file.vectors {
+            if let Err(e) = check_vector(vector) {
+                panic!("{}: {}", vector.name, e);
+            }
+        }
+    }
+
+    #[test]
+    fn only_the_recipient_decrypts() {
+        let recipient = SecretKey::random(&mut rand::rngs::OsRng);
+        let ciphertext = encrypt(&compressed(&recipient.public_key()), b"counter").unwrap();
+
+        assert_eq!(decrypt(&recipient.to_bytes(), &ciphertext).unwrap(), b"counter");
+        let other = SecretKey::random(&mut rand::rngs::OsRng);
+        assert!(decrypt(&other.to_bytes(), &ciphertext).is_err());
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let recipient = SecretKey::random(&mut rand::rngs::OsRng);
+        let mut ciphertext = encrypt(&compressed(&recipient.public_key()), b"counter").unwrap();
+
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 1;
+        assert!(decrypt(&recipient.to_bytes(), &ciphertext).is_err());
+        assert!(decrypt(&recipient.to_bytes(), &ciphertext[..PUBLIC_KEY_LEN + IV_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn malformed_public_keys_are_rejected() {
+        assert!(validate_public_key(&[2; 32]).is_err());
+        assert!(validate_public_key(&[9; PUBLIC_KEY_LEN]).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Golden fixtures of the EVM encoding of ARM transactions.
+//
+// For each case, `fixtures/transactions/` holds:
+//
+//   <case>.arm.bincode    the canonical ARM transaction, bincode-encoded
+//   <case>.evm.json       its `ProtocolAdapter::Transaction`, as JSON
+//   <case>.calldata.hex   the ABI-encoded `execute` calldata
+//
+// The tests re-encode every committed case and fail on any byte difference, so a
+// change to the conversion, the bindings or the ABI shows up as a fixture diff
+// instead of a revert on chain. The canonical ARM transactions are the seeded
+// counter init and increment (see `seeded`), proved in dev mode, so regenerating
+// them gives the same bytes until something in the encoding changes;
+// `FIXTURES_REGENERATE=1 cargo test fixtures::` rewrites them.
+//
+// `arm-test-transaction` has no ARM transaction: it is the ARM test transaction
+// the frontend's `ProtocolAdapterService` submits, kept as EVM JSON, and its JSON
+// and calldata are checked against each other.
+
+use std::path::{Path, PathBuf};
+
+use alloy::primitives::hex;
+use alloy::sol_types::SolCall;
+use arm_risc0::merkle_path::MerklePath;
+use arm_risc0::transaction::Transaction as ArmTransaction;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+
+use crate::counter::CounterTransition;
+use crate::seeded::{self, SeededRng};
+use crate::verification::{self, ExpectedImageIds};
+
+pub const FIXTURES_DIR: &str = "fixtures/transactions";
+
+const ARM_SUFFIX: &str = ".arm.bincode";
+const EVM_SUFFIX: &str = ".evm.json";
+const CALLDATA_SUFFIX: &str = ".calldata.hex";
+
+struct Encodings {
+    arm: Vec<u8>,
+    evm_json: Vec<u8>,
+    calldata: Vec<u8>, // hex, as stored
+}
+
+fn encode(tx: &ArmTransaction) -> Result<Encodings, String> {
+    let arm = bincode::serialize(tx).map_err(|e| format!("Failed to encode ARM transaction: {}", e))?;
+    let (evm_json, calldata) = encode_evm(ProtocolAdapter::Transaction::from(tx.clone()))?;
+    Ok(Encodings {
+        arm,
+        evm_json,
+        calldata,
+    })
+}
+
+// The JSON and the hex `execute` calldata of an EVM transaction, as stored.
+fn encode_evm(evm_tx: ProtocolAdapter::Transaction) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let evm_json =
+        serde_json::to_string_pretty(&evm_tx).map_err(|e| format!("Failed to encode EVM transaction: {}", e))?;
+    let calldata = ProtocolAdapter::executeCall { transaction: evm_tx }.abi_encode();
+    Ok((
+        (evm_json + "\n").into_bytes(),
+        format!("0x{}\n", hex::encode(calldata)).into_bytes(),
+    ))
+}
+
+fn case_path(dir: &Path, case: &str, suffix: &str) -> PathBuf {
+    dir.join(format!("{}{}", case, suffix))
+}
+
+// Cases proved from the seeded counter flow
+const CANONICAL_CASES: [&str; 2] = ["counter-init", "counter-increment"];
+
+fn canonical_transactions() -> Result<Vec<(&'static str, ArmTransaction)>, String> {
+    let mut rng = SeededRng::new(seeded::GOLDEN_SEED);
+    let (init_tx, counter, nf_key, _) = seeded::create_init_counter_tx(&mut rng);
+    let (increment_tx, _, _) =
+        seeded::create_transition_tx(&mut rng, counter, nf_key, MerklePath::default(), CounterTransition::Increment)?;
+    Ok(CANONICAL_CASES.into_iter().zip([init_tx, increment_tx]).collect())
+}
+
+// Proves the canonical transactions and writes every fixture of `dir` anew.
+pub fn regenerate(dir: &str) -> Result<(), String> {
+    if !verification::is_dev_mode() {
+        return Err("Fixtures are proved in dev mode only; set RISC0_DEV_MODE=1".to_string());
+    }
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    for (case, tx) in canonical_transactions()? {
+        verification::verify_arm_proofs(&tx, &ExpectedImageIds::counter()).into_result()?;
+        let encodings = encode(&tx)?;
+        for (suffix, bytes) in [
+            (ARM_SUFFIX, &encodings.arm),
+            (EVM_SUFFIX, &encodings.evm_json),
+            (CALLDATA_SUFFIX, &encodings.calldata),
+        ] {
+            let path = case_path(dir, case, suffix);
+            std::fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        println!("wrote {}", case);
+    }
+    Ok(())
+}
+
+fn check_bytes(path: &Path, actual: &[u8]) -> Result<(), String> {
+    let expected = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if expected == actual {
+        return Ok(());
+    }
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected.len().min(actual.len()));
+    Err(format!(
+        "{} differs at byte {} ({} bytes stored, {} re-encoded)",
+        path.display(),
+        offset,
+        expected.len(),
+        actual.len()
+    ))
+}
+
+fn check_case(dir: &Path, case: &str) -> Result<(), String> {
+    let arm_path = case_path(dir, case, ARM_SUFFIX);
+    let (evm_json, calldata) = if arm_path.exists() {
+        let arm = std::fs::read(&arm_path).map_err(|e| format!("Failed to read {}: {}", arm_path.display(), e))?;
+        let tx: ArmTransaction = bincode::deserialize(&arm)
+            .map_err(|e| format!("Invalid ARM transaction {}: {}", arm_path.display(), e))?;
+        let encodings = encode(&tx)?;
+        check_bytes(&arm_path, &encodings.arm)?;
+        (encodings.evm_json, encodings.calldata)
+    } else {
+        // Without an ARM transaction, the stored JSON is the source
+        let evm_path = case_path(dir, case, EVM_SUFFIX);
+        let contents =
+            std::fs::read(&evm_path).map_err(|e| format!("Failed to read {}: {}", evm_path.display(), e))?;
+        let evm_tx: ProtocolAdapter::Transaction = serde_json::from_slice(&contents)
+            .map_err(|e| format!("Invalid EVM transaction {}: {}", evm_path.display(), e))?;
+        encode_evm(evm_tx)?
+    };
+
+    check_bytes(&case_path(dir, case, EVM_SUFFIX), &evm_json)?;
+    check_bytes(&case_path(dir, case, CALLDATA_SUFFIX), &calldata)?;
+
+    // The calldata must also decode to the same transaction
+    let calldata =
+        hex::decode(String::from_utf8_lossy(&calldata).trim()).map_err(|e| format!("Invalid calldata hex: {}", e))?;
+    let call = ProtocolAdapter::executeCall::abi_decode(&calldata)
+        .map_err(|e| format!("Calldata does not decode as execute: {}", e))?;
+    if call.abi_encode() != calldata {
+        return Err("Decoded calldata does not re-encode to the same bytes".to_string());
+    }
+    Ok(())
+}
+
+// Checks every case in `dir` and returns the number of failures.
+pub fn run_checks(dir: &str) -> Result<usize, String> {
+    let dir = Path::new(dir);
+    let mut cases: Vec<String> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.strip_suffix(EVM_SUFFIX).map(str::to_string))
+        .collect();
+    if cases.is_empty() {
+        return Err(format!("No {} fixtures in {}", EVM_SUFFIX, dir.display()));
+    }
+    cases.sort();
+
+    let mut failures = 0;
+    for case in &cases {
+        match check_case(dir, case) {
+            Ok(()) => println!("PASS {}", case),
+            Err(e) => {
+                println!("FAIL {}: {}", case, e);
+                failures += 1;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The transaction the frontend submits, with no ARM transaction behind it
+    const TEST_TRANSACTION_CASE: &str = "arm-test-transaction";
+
+    // Copies the committed files of `case` into a fresh directory.
+    fn copy_case(case: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fixtures-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for suffix in [EVM_SUFFIX, CALLDATA_SUFFIX] {
+            std::fs::copy(case_path(Path::new(FIXTURES_DIR), case, suffix), case_path(&dir, case, suffix)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn committed_fixtures_pass() {
+        if std::env::var("FIXTURES_REGENERATE").as_deref() == Ok("1") {
+            verification::use_dev_mode();
+            regenerate(FIXTURES_DIR).unwrap();
+        }
+        for case in CANONICAL_CASES.into_iter().chain([TEST_TRANSACTION_CASE]) {
+            if let Err(e) = check_case(Path::new(FIXTURES_DIR), case) {
+                panic!("{} (regenerate with FIXTURES_REGENERATE=1 cargo test fixtures::): {}", case, e);
+            }
+        }
+        assert_eq!(run_checks(FIXTURES_DIR), Ok(0));
+    }
+
+    #[test]
+    fn test_transaction_fixture_matches_its_calldata() {
+        assert_eq!(check_case(Path::new(FIXTURES_DIR), TEST_TRANSACTION_CASE), Ok(()));
+    }
+
+    #[test]
+    fn changed_calldata_fails_its_case() {
+        let dir = copy_case(TEST_TRANSACTION_CASE);
+        let path = case_path(&dir, TEST_TRANSACTION_CASE, CALLDATA_SUFFIX);
+        let mut calldata = std::fs::read(&path).unwrap();
+        // The last hex digit, before the trailing newline
+        let last = calldata.len() - 2;
+        calldata[last] = if calldata[last] == b'0' { b'1' } else { b'0' };
+        std::fs::write(&path, calldata).unwrap();
+
+        assert_eq!(run_checks(dir.to_str().unwrap()), Ok(1));
+        assert!(check_case(&dir, TEST_TRANSACTION_CASE).unwrap_err().contains("differs at byte"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+// Forwarder calls: calls from the Protocol Adapter into external EVM contracts,
+// triggered by a resource.
+//
+// A created resource declares at most one call, in the app data of its logic
+// instance, as the first blob:
+//
+//   abi.encode(address untrustedForwarder, bytes input, bytes expectedOutput)
+//
+// When the transaction is executed, the Protocol Adapter calls
+// `forwardCall(input)` on the forwarder and reverts with ForwarderCallOutputMismatch
+// unless the call returns `expectedOutput`. Executed calls are logged as
+// ForwarderCallExecuted. Blobs are committed by the app's logic guest, like any app
+// data, so only apps that commit app data can declare forwarder calls.
+//
+// Before proving, the call is dry-run with `eth_call` from the Protocol Adapter's
+// address, so a call that would revert or return something else fails early
+// instead of after proving.
+
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::providers::Provider;
+use alloy::sol;
+use alloy::sol_types::SolValue;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::{AppDataBlob, DeletionCriterion};
+
+sol! {
+    #[sol(rpc)]
+    interface IForwarder {
+        function forwardCall(bytes calldata input) external returns (bytes memory output);
+    }
+}
+
+// `{"target": "0x…", "input": "0x…", "expected_output": "0x…"}`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ForwarderCall {
+    pub target: Address,
+    pub input: Bytes,
+    pub expected_output: Bytes,
+}
+
+impl ForwarderCall {
+    // The call is executed with the transaction; the ForwarderCallExecuted event
+    // keeps the record, so the blob itself may be dropped afterwards.
+    pub fn to_blob(&self) -> AppDataBlob {
+        AppDataBlob {
+            deletion_criterion: DeletionCriterion::Immediately,
+            bytes: (self.target, self.input.clone(), self.expected_output.clone()).abi_encode_params(),
+        }
+    }
+
+    pub fn from_blob(bytes: &[u8]) -> Result<Self, String> {
+        let (target, input, expected_output) = <(Address, Bytes, Bytes)>::abi_decode_params(bytes)
+            .map_err(|e| format!("Blob is not a forwarder call: {}", e))?;
+        Ok(ForwarderCall {
+            target,
+            input,
+            expected_output,
+        })
+    }
+
+    // Runs the call with `eth_call` as the Protocol Adapter would, and checks its
+    // output. Returns the output.
+    pub async fn dry_run<P: Provider>(&self, provider: &P, protocol_adapter: Address) -> Result<Bytes, String> {
+        if provider
+            .get_code_at(self.target)
+            .await
+            .map_err(|e| format!("Failed to get code of {}: {}", self.target, e))?
+            .is_empty()
+        {
+            return Err(format!("Forwarder {} has no code", self.target));
+        }
+        let output = IForwarder::new(self.target, provider)
+            .forwardCall(self.input.clone())
+            .from(protocol_adapter)
+            .call()
+            .await
+            .map_err(|e| format!("Forwarder call to {} failed: {}", self.target, e))?;
+        if output != self.expected_output {
+            return Err(format!(
+                "Forwarder call output mismatch: expected {}, got {}",
+                self.expected_output, output
+            ));
+        }
+        Ok(output)
+    }
+}
+
+// Dry-runs a call against the configured Protocol Adapter.
+pub async fn dry_run(call: &ForwarderCall) -> Result<Bytes, String> {
+    let adapter = protocol_adapter();
+    call.dry_run(adapter.provider(), *adapter.address()).await
+}
+
+#[derive(Serialize)]
+pub struct ExecutedForwarderCall {
+    pub forwarder: Address,
+    pub input: Bytes,
+    pub output: Bytes,
+}
+
+// The forwarder calls the Protocol Adapter executed in a transaction.
+pub async fn executed_calls(tx_hash: &str) -> Result<Vec<ExecutedForwarderCall>, String> {
+    let hash: B256 = tx_hash
+        .parse()
+        .map_err(|e| format!("Invalid transaction hash {}: {}", tx_hash, e))?;
+    let adapter = protocol_adapter();
+    let receipt = adapter
+        .provider()
+        .get_transaction_receipt(hash)
+        .await
+        .map_err(|e| format!("Failed to get receipt of {}: {}", tx_hash, e))?
+        .ok_or_else(|| format!("Transaction {} not found", tx_hash))?;
+
+    Ok(receipt
+        .inner
+        .logs()
+        .iter()
+        .filter(|log| log.address() == *adapter.address())
+        .filter_map(|log| log.log_decode::<ProtocolAdapter::ForwarderCallExecuted>().ok())
+        .map(|log| {
+            let event = log.inner.data;
+            ExecutedForwarderCall {
+                forwarder: event.untrustedForwarder,
+                input: event.input,
+                output: event.output,
+            }
+        })
+        .collect())
+}
+
+// First of the accounts Anvil funds by default.
+pub const ANVIL_DEFAULT_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::network::{ReceiptResponse, TransactionBuilder};
+    use alloy::primitives::{hex, keccak256, U256};
+    use alloy::providers::ProviderBuilder;
+    use alloy::rpc::types::TransactionRequest;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::sol_types::SolCall;
+
+    // Runtime of the mock forwarder: returns its calldata after the selector, which for
+    // `forwardCall(bytes)` is the ABI encoding of `input` as the `bytes` return value.
+    //
+    //   PUSH1 4, CALLDATASIZE, SUB          n = calldatasize - 4
+    //   DUP1, PUSH1 4, PUSH1 0, CALLDATACOPY
+    //   PUSH1 0, RETURN                     return memory[0..n]
+    //
+    // The init code copies the 13-byte runtime to memory and returns it.
+    const MOCK_FORWARDER_INIT_CODE: &str = "600d80600b6000396000f3600436038060046000376000f3";
+
+    fn word(value: usize) -> Vec<u8> {
+        U256::from(value).to_be_bytes::<32>().to_vec()
+    }
+
+    fn sample_call() -> ForwarderCall {
+        ForwarderCall {
+            target: Address::repeat_byte(0xf0),
+            input: Bytes::from_static(b"input"),
+            expected_output: Bytes::from_static(b"expected output"),
+        }
+    }
+
+    #[test]
+    fn blob_is_the_abi_encoded_call() {
+        let call = sample_call();
+        let blob = call.to_blob();
+
+        // address, offset of input, offset of expectedOutput, then each as length || padded data
+        let mut expected = [vec![0; 12], call.target.to_vec()].concat();
+        expected.extend(word(3 * 32));
+        expected.extend(word(5 * 32));
+        for bytes in [&call.input, &call.expected_output] {
+            expected.extend(word(bytes.len()));
+            expected.extend([bytes.to_vec(), vec![0; 32 - bytes.len()]].concat());
+        }
+        assert_eq!(blob.bytes, expected);
+        assert_eq!(ForwarderCall::from_blob(&blob.bytes), Ok(call));
+        assert!(ForwarderCall::from_blob(&blob.bytes[..64]).is_err());
+    }
+
+    #[test]
+    fn forward_call_calldata_encodes_the_input() {
+        let call = sample_call();
+        let calldata = IForwarder::forwardCallCall {
+            input: call.input.clone(),
+        }
+        .abi_encode();
+
+        let selector = &keccak256("forwardCall(bytes)")[..4];
+        let mut expected = selector.to_vec();
+        expected.extend(word(32));
+        expected.extend(word(call.input.len()));
+        expected.extend([call.input.to_vec(), vec![0; 32 - call.input.len()]].concat());
+        assert_eq!(calldata, expected);
+    }
+
+    // Sends the call as a transaction from `from`, which Anvil impersonates, and checks
+    // that it succeeds. This is the call the Protocol Adapter makes while executing.
+    async fn send_impersonated(rpc_url: &str, call: &ForwarderCall, from: Address) -> B256 {
+        let provider = ProviderBuilder::new().connect_http(rpc_url.parse().unwrap());
+        provider
+            .raw_request::<_, serde_json::Value>("anvil_impersonateAccount".into(), (from,))
+            .await
+            .unwrap();
+        provider
+            .raw_request::<_, serde_json::Value>("anvil_setBalance".into(), (from, U256::from(10).pow(U256::from(18))))
+            .await
+            .unwrap();
+
+        let request = IForwarder::new(call.target, &provider)
+            .forwardCall(call.input.clone())
+            .from(from)
+            .into_transaction_request();
+        let receipt = provider.send_transaction(request).await.unwrap().get_receipt().await.unwrap();
+        assert!(receipt.status(), "forwarder call {} reverted", receipt.transaction_hash());
+        receipt.transaction_hash()
+    }
+
+    // Deploys an echo forwarder on a local Anvil node (ANVIL_RPC_URL, default
+    // http://127.0.0.1:8545), dry-runs a call with the expected and with a wrong
+    // output, and sends it as a transaction from the Protocol Adapter's address
+    // (PROTOCOL_ADAPTER_ADDRESS_SEPOLIA, or a fixed address on a bare node).
+    //
+    // Executing the call through `ProtocolAdapter.execute` needs a proved transaction
+    // whose logic instance commits the call. No guest in arm-risc0 v0.3.0 takes app
+    // data, so that step is blocked until an app logic that commits app data exists.
+    #[tokio::test]
+    #[ignore = "needs a local Anvil node"]
+    async fn mock_forwarder_call_on_anvil() {
+        let rpc_url = std::env::var("ANVIL_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".to_string());
+        let key = std::env::var("ANVIL_PRIVATE_KEY").unwrap_or_else(|_| ANVIL_DEFAULT_KEY.to_string());
+        let signer: PrivateKeySigner = key.parse().unwrap();
+        let provider = ProviderBuilder::new().wallet(signer).connect_http(rpc_url.parse().unwrap());
+
+        // Step 1: Deploy the mock forwarder
+        let init_code = hex::decode(MOCK_FORWARDER_INIT_CODE).unwrap();
+        let receipt = provider
+            .send_transaction(TransactionRequest::default().with_deploy_code(init_code))
+            .await
+            .unwrap()
+            .get_receipt()
+            .await
+            .unwrap();
+        let forwarder = receipt.contract_address().expect("deployment receipt has a contract address");
+
+        // Step 2: Dry-run with the expected output and send the call, both from the
+        // Protocol Adapter's address
+        let input = Bytes::from(U256::from(42).to_be_bytes::<32>().to_vec());
+        let call = ForwarderCall {
+            target: forwarder,
+            input: input.clone(),
+            expected_output: input.clone(),
+        };
+        let adapter_address: Address = std::env::var("PROTOCOL_ADAPTER_ADDRESS_SEPOLIA")
+            .map(|address| address.parse().unwrap())
+            .unwrap_or(Address::repeat_byte(0xad));
+        assert_eq!(call.dry_run(&provider, adapter_address).await, Ok(input));
+        send_impersonated(&rpc_url, &call, adapter_address).await;
+
+        // Step 3: A wrong expected output and a target without code are rejected
+        let wrong = ForwarderCall {
+            expected_output: Bytes::from_static(b"wrong"),
+            ..call.clone()
+        };
+        assert!(wrong.dry_run(&provider, adapter_address).await.unwrap_err().contains("mismatch"));
+        let missing = ForwarderCall {
+            target: Address::repeat_byte(0x11),
+            ..call
+        };
+        assert!(missing.dry_run(&provider, adapter_address).await.unwrap_err().contains("no code"));
+    }
+}
@@ -0,0 +1,525 @@
+// Intent pool and a local solver for swaps.
+//
+// An intent is a partial transaction: the owner proves an action that consumes what
+// they give and creates what they want for themselves, so it is unbalanced on its
+// own, e.g. "give 5 A, want 3 B". The owner submits the proved actions together with
+// their delta witness (a bincode `Transaction` whose delta is still a witness).
+//
+// Assets are named by their kind, the resource logic and label, and a quantity. A
+// partial transaction is checked against its terms when it is submitted: the sum of
+// its unit deltas, less the blinding its delta witness commits to, must be exactly
+// what it gives minus what it wants. Two open intents of different owners match
+// when each gives exactly what the other wants. The solver composes the two partial
+// transactions into one with a combined delta proof, which only verifies if the swap
+// balances, checks it locally and submits it. If settling fails because one side
+// already spent a resource it offered, only that intent fails and the other is open
+// again. Intents expire at their deadline and can be cancelled by their owner until
+// they are matched.
+
+use std::collections::HashMap;
+
+use alloy::network::ReceiptResponse;
+use alloy::primitives::hex;
+use arm_risc0::delta_proof::DeltaWitness;
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use arm_risc0::utils;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use k256::elliptic_curve::sec1::FromEncodedPoint;
+use k256::{AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar};
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+
+use crate::batch;
+use crate::counter_audit;
+use crate::verification::{self, ExpectedImageIds};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Asset {
+    pub logic_ref: String, // hex
+    pub label_ref: String, // hex
+    pub quantity: u128,
+}
+
+impl Asset {
+    // How the asset appears in the signed message, e.g. "5 0x<logic_ref>/0x<label_ref>"
+    fn message_form(&self) -> String {
+        format!("{} {}/{}", self.quantity, self.logic_ref.to_lowercase(), self.label_ref.to_lowercase())
+    }
+
+    fn same_kind(&self, other: &Asset) -> bool {
+        self.logic_ref.eq_ignore_ascii_case(&other.logic_ref) && self.label_ref.eq_ignore_ascii_case(&other.label_ref)
+    }
+
+    fn satisfies(&self, wanted: &Asset) -> bool {
+        self.same_kind(wanted) && self.quantity == wanted.quantity
+    }
+
+    pub fn logic_digest(&self) -> Result<Digest, String> {
+        Digest::from_hex(self.logic_ref.trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid logic_ref {}: {}", self.logic_ref, e))
+    }
+
+    // Kind point of the asset, as the compliance circuit derives it from a resource.
+    fn kind(&self) -> Result<ProjectivePoint, String> {
+        let label_ref = Digest::from_hex(self.label_ref.trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid label_ref {}: {}", self.label_ref, e))?;
+        let (_, owner) = NullifierKey::random_pair();
+        let resource =
+            Resource::create(self.logic_digest()?, label_ref, 0, Digest::default(), true, Digest::default(), owner);
+        Ok(resource.kind())
+    }
+
+    // What the asset contributes to a delta: positive when consumed
+    fn delta(&self) -> Result<ProjectivePoint, String> {
+        Ok(self.kind()? * Scalar::from(self.quantity))
+    }
+}
+
+// Lines the owner signs along with `Action: SUBMIT_INTENT`.
+pub fn terms_lines(give: &Asset, want: &Asset, expires_at: i64) -> [String; 3] {
+    [
+        format!("Give: {}", give.message_form()),
+        format!("Want: {}", want.message_form()),
+        format!("Expires: {}", expires_at),
+    ]
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IntentStatus {
+    Open,
+    Matched { with: String, tx_hash: Option<String> },
+    Cancelled,
+    Expired,
+    // The composed transaction did not verify or could not be submitted
+    Failed { error: String },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Intent {
+    pub id: String,
+    pub owner: String,
+    pub give: Asset,
+    pub want: Asset,
+    pub expires_at: i64, // unix seconds
+    #[serde(flatten)]
+    pub status: IntentStatus,
+}
+
+// The proved part of an intent, kept out of the listing.
+#[derive(Clone)]
+pub struct PartialTransaction {
+    pub tx: ArmTransaction,
+    pub delta_witness: DeltaWitness,
+}
+
+impl PartialTransaction {
+    // Decodes a base64 bincode transaction and checks the proofs of its actions. The
+    // delta can only be checked once it is composed with a matching intent.
+    pub fn decode(encoded: &str, expected: &ExpectedImageIds) -> Result<Self, String> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| format!("Partial transaction is not valid base64: {}", e))?;
+        let tx: ArmTransaction =
+            bincode::deserialize(&bytes).map_err(|e| format!("Invalid partial transaction: {}", e))?;
+        if tx.actions.is_empty() {
+            return Err("Partial transaction has no actions".to_string());
+        }
+        let delta_witness = match &tx.delta_proof {
+            Delta::Witness(witness) => witness.clone(),
+            Delta::Proof(_) => return Err("Partial transaction must carry its delta witness".to_string()),
+        };
+        verification::verify_actions(&tx, expected).into_result()?;
+        Ok(PartialTransaction { tx, delta_witness })
+    }
+
+    // Checks that the partial consumes `give` and creates `want` and is otherwise
+    // balanced. Each compliance unit's delta is
+    // kind(consumed) * quantity - kind(created) * quantity + rcv * G, and the delta
+    // witness holds the sum of the rcv.
+    pub fn check_terms(&self, give: &Asset, want: &Asset) -> Result<(), String> {
+        let mut delta = ProjectivePoint::IDENTITY;
+        for action in &self.tx.actions {
+            for unit in &action.compliance_units {
+                let instance = unit.get_instance();
+                let (x, y) = (utils::words_to_bytes(&instance.delta_x), utils::words_to_bytes(&instance.delta_y));
+                let point =
+                    EncodedPoint::from_affine_coordinates(FieldBytes::from_slice(x), FieldBytes::from_slice(y), false);
+                let point = Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&point))
+                    .ok_or_else(|| "Compliance unit delta is not a curve point".to_string())?;
+                delta += ProjectivePoint::from(point);
+            }
+        }
+        let blinding = ProjectivePoint::from(*self.delta_witness.signing_key.verifying_key().as_affine());
+        if delta - blinding != give.delta()? - want.delta()? {
+            return Err(format!(
+                "Partial transaction does not give {} and want {}",
+                give.message_form(),
+                want.message_form()
+            ));
+        }
+        Ok(())
+    }
+
+    fn nullifiers(&self) -> Vec<String> {
+        self.tx
+            .actions
+            .iter()
+            .flat_map(|action| action.compliance_units.iter())
+            .map(|unit| format!("0x{}", hex::encode(unit.get_instance().consumed_nullifier.as_bytes())))
+            .collect()
+    }
+}
+
+// Why a match did not settle. `offender` is the index, in the pair passed to
+// `settle_match`, of the intent whose partial transaction was at fault, if known.
+pub struct SettleError {
+    pub error: String,
+    pub offender: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct IntentPool {
+    intents: Vec<Intent>,
+    partials: HashMap<String, PartialTransaction>,
+}
+
+impl IntentPool {
+    pub fn submit(
+        &mut self,
+        owner: &str,
+        give: Asset,
+        want: Asset,
+        expires_at: i64,
+        now: i64,
+        partial: Option<PartialTransaction>,
+    ) -> Result<String, String> {
+        if expires_at <= now {
+            return Err("Intent is already expired".to_string());
+        }
+        if give.quantity == 0 || want.quantity == 0 {
+            return Err("Intent quantities must be non-zero".to_string());
+        }
+        if give.same_kind(&want) {
+            return Err("Intent gives and wants the same asset".to_string());
+        }
+        if let Some(partial) = &partial {
+            partial.check_terms(&give, &want)?;
+        }
+
+        let id = format!("0x{}", hex::encode(rand::random::<[u8; 16]>()));
+        self.intents.push(Intent {
+            id: id.clone(),
+            owner: owner.to_string(),
+            give,
+            want,
+            expires_at,
+            status: IntentStatus::Open,
+        });
+        if let Some(partial) = partial {
+            self.partials.insert(id.clone(), partial);
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Intent> {
+        self.intents.iter().find(|intent| intent.id == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut Intent, String> {
+        self.intents
+            .iter_mut()
+            .find(|intent| intent.id == id)
+            .ok_or_else(|| format!("Unknown intent {}", id))
+    }
+
+    pub fn list(&self) -> &[Intent] {
+        &self.intents
+    }
+
+    pub fn cancel(&mut self, id: &str, owner: &str) -> Result<(), String> {
+        let intent = self.get_mut(id)?;
+        if !intent.owner.eq_ignore_ascii_case(owner) {
+            return Err(format!("{} does not own intent {}", owner, id));
+        }
+        if intent.status != IntentStatus::Open {
+            return Err(format!("Intent {} is not open", id));
+        }
+        intent.status = IntentStatus::Cancelled;
+        self.partials.remove(id);
+        Ok(())
+    }
+
+    // Marks open intents past their deadline as expired. Returns how many expired.
+    pub fn expire(&mut self, now: i64) -> usize {
+        let mut expired = 0;
+        for intent in &mut self.intents {
+            if intent.status == IntentStatus::Open && intent.expires_at <= now {
+                intent.status = IntentStatus::Expired;
+                self.partials.remove(&intent.id);
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    // The oldest open intent of another owner that swaps exactly with `id`.
+    pub fn find_match(&self, id: &str) -> Option<String> {
+        let intent = self.get(id).filter(|intent| intent.status == IntentStatus::Open)?;
+        self.intents
+            .iter()
+            .filter(|other| other.id != intent.id && other.status == IntentStatus::Open)
+            .filter(|other| !other.owner.eq_ignore_ascii_case(&intent.owner))
+            .find(|other| other.give.satisfies(&intent.want) && intent.give.satisfies(&other.want))
+            .map(|other| other.id.clone())
+    }
+
+    // Marks both intents matched for settlement and returns their partial
+    // transactions. The pool keeps its copies until the match is settled.
+    pub fn take_match(&mut self, a: &str, b: &str) -> Result<(PartialTransaction, PartialTransaction), String> {
+        let (pa, pb) = match (self.partials.get(a), self.partials.get(b)) {
+            (Some(pa), Some(pb)) => (pa.clone(), pb.clone()),
+            _ => return Err("Matched intents have no partial transactions".to_string()),
+        };
+        for (id, other) in [(a, b), (b, a)] {
+            self.get_mut(id)?.status = IntentStatus::Matched {
+                with: other.to_string(),
+                tx_hash: None,
+            };
+        }
+        Ok((pa, pb))
+    }
+
+    // Records the outcome of `settle_match` for `ids`, in the order the partials were
+    // passed to it. On failure only the offending intent fails; the others are open
+    // again and keep their partial transactions.
+    pub fn settle(&mut self, ids: [&str; 2], result: &Result<String, SettleError>) {
+        for (i, id) in ids.into_iter().enumerate() {
+            let Ok(intent) = self.get_mut(id) else { continue };
+            intent.status = match (result, &intent.status) {
+                (Ok(tx_hash), IntentStatus::Matched { with, .. }) => IntentStatus::Matched {
+                    with: with.clone(),
+                    tx_hash: Some(tx_hash.clone()),
+                },
+                (Err(e), _) if e.offender == Some(i) => IntentStatus::Failed { error: e.error.clone() },
+                (Err(_), _) => IntentStatus::Open,
+                (Ok(_), status) => status.clone(),
+            };
+            if intent.status != IntentStatus::Open {
+                self.partials.remove(id);
+            }
+        }
+    }
+}
+
+// Composes two matched partial transactions into one balanced transaction with a
+// combined delta proof.
+pub fn compose(a: PartialTransaction, b: PartialTransaction) -> ArmTransaction {
+    let witnesses = [a.delta_witness, b.delta_witness];
+    let actions = a.tx.actions.into_iter().chain(b.tx.actions).collect();
+    batch::compose_transaction(actions, &witnesses)
+}
+
+// Composes, verifies and submits a match. Returns the transaction hash.
+pub async fn settle_match(
+    a: PartialTransaction,
+    b: PartialTransaction,
+    expected: ExpectedImageIds,
+) -> Result<String, SettleError> {
+    let unattributed = |error: String| SettleError { error, offender: None };
+
+    // Step 1: A side that already spent a resource it offered is at fault
+    for (offender, partial) in [&a, &b].into_iter().enumerate() {
+        for nullifier in partial.nullifiers() {
+            if counter_audit::is_nullifier_published(&nullifier).await.map_err(unattributed)? {
+                return Err(SettleError {
+                    error: format!("Resource with nullifier {} was already consumed", nullifier),
+                    offender: Some(offender),
+                });
+            }
+        }
+    }
+
+    // Step 2: Compose, verify and submit
+    settle_checked(a, b, expected).await.map_err(unattributed)
+}
+
+async fn settle_checked(
+    a: PartialTransaction,
+    b: PartialTransaction,
+    expected: ExpectedImageIds,
+) -> Result<String, String> {
+    let adapter = protocol_adapter();
+    let verifier_selector = verification::get_verifier_selector(&adapter).await?;
+
+    let evm_tx = tokio::task::spawn_blocking(move || {
+        let tx = compose(a, b);
+        let evm_tx = ProtocolAdapter::Transaction::from(tx.clone());
+        verification::verify_transaction(&tx, &evm_tx, &expected, Some(verifier_selector))?;
+        Ok::<_, String>(evm_tx)
+    })
+    .await
+    .map_err(|e| format!("Failed to compose intents: {}", e))??;
+
+    let pending_tx = adapter
+        .execute(evm_tx)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to submit swap transaction: {}", e))?;
+    let receipt = pending_tx
+        .get_receipt()
+        .await
+        .map_err(|e| format!("Failed to get swap transaction receipt: {}", e))?;
+    let tx_hash = format!("0x{}", hex::encode(receipt.transaction_hash()));
+    if receipt.status() {
+        Ok(tx_hash)
+    } else {
+        Err(format!("Swap transaction {} reverted", tx_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000;
+
+    fn asset(label: u8, quantity: u128) -> Asset {
+        Asset {
+            logic_ref: format!("0x{}", "01".repeat(32)),
+            label_ref: format!("0x{}", hex::encode([label; 32])),
+            quantity,
+        }
+    }
+
+    // A partial transaction without actions, which balances nothing
+    fn empty_partial() -> PartialTransaction {
+        let delta_witness = DeltaWitness::from_bytes(&[1; 32]);
+        PartialTransaction {
+            tx: ArmTransaction::create(vec![], Delta::Witness(delta_witness.clone())),
+            delta_witness,
+        }
+    }
+
+    // Two matched intents. Their partials skip the terms check, which needs proofs.
+    fn matched_pool() -> (IntentPool, String, String) {
+        let mut pool = IntentPool::default();
+        let alice = pool.submit("alice", asset(0xa, 5), asset(0xb, 3), NOW + 60, NOW, None).unwrap();
+        let bob = pool.submit("bob", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+        for id in [&alice, &bob] {
+            pool.partials.insert(id.clone(), empty_partial());
+        }
+        assert_eq!(pool.find_match(&alice), Some(bob.clone()));
+        pool.take_match(&alice, &bob).unwrap();
+        (pool, alice, bob)
+    }
+
+    fn status(pool: &IntentPool, id: &str) -> IntentStatus {
+        pool.get(id).unwrap().status.clone()
+    }
+
+    #[test]
+    fn partial_that_does_not_match_its_terms_is_rejected() {
+        let mut pool = IntentPool::default();
+        let result = pool.submit("alice", asset(0xa, 5), asset(0xb, 3), NOW + 60, NOW, Some(empty_partial()));
+
+        assert!(result.unwrap_err().contains("does not give"));
+        assert!(pool.list().is_empty());
+    }
+
+    #[test]
+    fn settled_match_records_the_transaction() {
+        let (mut pool, alice, bob) = matched_pool();
+        pool.settle([&alice, &bob], &Ok("0xabc".to_string()));
+
+        for (id, with) in [(&alice, &bob), (&bob, &alice)] {
+            let expected = IntentStatus::Matched {
+                with: with.clone(),
+                tx_hash: Some("0xabc".to_string()),
+            };
+            assert_eq!(status(&pool, id), expected);
+        }
+        assert!(pool.partials.is_empty());
+    }
+
+    #[test]
+    fn only_the_offending_intent_fails() {
+        let (mut pool, alice, bob) = matched_pool();
+        let error = SettleError {
+            error: "already consumed".to_string(),
+            offender: Some(1),
+        };
+        pool.settle([&alice, &bob], &Err(error));
+
+        assert_eq!(status(&pool, &alice), IntentStatus::Open);
+        assert!(matches!(status(&pool, &bob), IntentStatus::Failed { .. }));
+        // alice's partial is kept, so she can be matched again
+        let carol = pool.submit("carol", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+        pool.partials.insert(carol.clone(), empty_partial());
+        assert_eq!(pool.find_match(&alice), Some(carol.clone()));
+        assert!(pool.take_match(&alice, &carol).is_ok());
+    }
+
+    #[test]
+    fn unattributed_failure_reopens_both_intents() {
+        let (mut pool, alice, bob) = matched_pool();
+        let error = SettleError {
+            error: "Failed to submit swap transaction".to_string(),
+            offender: None,
+        };
+        pool.settle([&alice, &bob], &Err(error));
+
+        assert_eq!(status(&pool, &alice), IntentStatus::Open);
+        assert_eq!(status(&pool, &bob), IntentStatus::Open);
+        assert_eq!(pool.find_match(&alice), Some(bob));
+    }
+
+    #[test]
+    fn exact_swaps_of_different_owners_match_oldest_first() {
+        let mut pool = IntentPool::default();
+        let alice = pool.submit("alice", asset(0xa, 5), asset(0xb, 3), NOW + 60, NOW, None).unwrap();
+        pool.submit("carol", asset(0xb, 3), asset(0xa, 4), NOW + 60, NOW, None).unwrap();
+        // Different quantities do not match
+        assert_eq!(pool.find_match(&alice), None);
+
+        // Nor do intents of the same owner
+        let alice_again = pool.submit("alice", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+        assert_eq!(pool.find_match(&alice), None);
+        assert_eq!(pool.find_match(&alice_again), None);
+
+        let bob = pool.submit("bob", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+        pool.submit("dave", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+        assert_eq!(pool.find_match(&alice), Some(bob));
+    }
+
+    #[test]
+    fn only_the_owner_cancels_an_open_intent() {
+        let mut pool = IntentPool::default();
+        let alice = pool.submit("alice", asset(0xa, 5), asset(0xb, 3), NOW + 60, NOW, None).unwrap();
+        let bob = pool.submit("bob", asset(0xb, 3), asset(0xa, 5), NOW + 60, NOW, None).unwrap();
+
+        assert!(pool.cancel(&bob, "alice").is_err());
+        pool.cancel(&bob, "bob").unwrap();
+        assert!(pool.cancel(&bob, "bob").is_err());
+        assert_eq!(pool.find_match(&alice), None);
+    }
+
+    #[test]
+    fn expired_intents_stop_matching() {
+        let mut pool = IntentPool::default();
+        let alice = pool.submit("alice", asset(0xa, 5), asset(0xb, 3), NOW + 60, NOW, None).unwrap();
+        let erin = pool.submit("erin", asset(0xb, 3), asset(0xa, 5), NOW + 10, NOW, None).unwrap();
+        assert_eq!(pool.find_match(&alice), Some(erin));
+
+        assert_eq!(pool.expire(NOW + 10), 1);
+        assert_eq!(pool.find_match(&alice), None);
+        assert_eq!(status(&pool, &alice), IntentStatus::Open);
+        // An intent that has already expired is not accepted
+        assert!(pool.submit("frank", asset(0xb, 3), asset(0xa, 5), NOW, NOW, None).is_err());
+    }
+}
@@ -1,12 +1,12 @@
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
-    routing::post,
+    extract::{Json, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
 use tower_http::cors::CorsLayer;
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use arm_risc0::resource::Resource;
 use arm_risc0::nullifier_key::NullifierKey;
@@ -18,20 +18,65 @@ use risc0_zkvm::sha::Digest;
 use evm_protocol_adapter_bindings::call::protocol_adapter;
 use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
 use alloy::primitives::hex;
+use alloy::network::ReceiptResponse;
+use alloy::providers::Provider;
 
 // Import the transaction generation function directly
 extern crate evm_protocol_adapter_bindings;
 
 // ARM imports - use same pattern as bindings
-use arm_risc0::transaction::{self, Transaction as ArmTransaction};
+use arm_risc0::transaction::Transaction as ArmTransaction;
 
 // ARM counter application imports
 extern crate app;
 
+mod app_data;
+mod artifacts;
+mod batch;
+mod bench;
+mod cli;
+mod composition;
+mod conversion;
+mod counter;
+mod counter_audit;
+mod counter_store;
+mod discovery;
+mod encryption;
+#[cfg(test)]
+mod fixtures;
+mod forwarder;
+mod intents;
+mod ownership;
+mod registry;
+mod resource_store;
+#[cfg(test)]
+mod seeded;
+mod stress;
+mod user_key;
+mod verification;
+use artifacts::{ArtifactStatus, ArtifactStore, ProvingInputs};
+use batch::{BatchConfig, BatchJob, BatchedIncrement, Batcher};
+use counter::{CounterIntent, CounterTransition};
+use counter_store::{CounterLocks, CounterStore};
+use intents::{IntentPool, PartialTransaction};
+use ownership::{CounterSelection, Ownership};
+use registry::{AppOperation, AppReceipt, AppResource, Registry};
+use discovery::PayloadStore;
+use resource_store::ResourceStore;
+use stress::{TransactionReport, TransactionShape};
+use verification::ExpectedImageIds;
+
 // State management (for future ARM counter operations)
 #[derive(Clone)]
 struct AppState {
-    counter_store: Arc<Mutex<HashMap<String, (Resource, NullifierKey)>>>,
+    counter_store: Arc<Mutex<CounterStore>>,
+    counter_locks: CounterLocks,
+    artifacts: Arc<ArtifactStore>,
+    batcher: Batcher,
+    registry: Arc<Registry>,
+    resources: Arc<Mutex<ResourceStore>>,
+    payloads: Arc<PayloadStore>,
+    intents: Arc<Mutex<IntentPool>>,
 }
 
 #[derive(Serialize)]
@@ -64,6 +109,110 @@ struct EmitTransactionRequest {
     timestamp: String,
 }
 
+#[derive(Deserialize)]
+struct EmitRealTransactionRequest {
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    // actions
+    #[serde(flatten)]
+    shape: TransactionShape,
+}
+
+#[derive(Deserialize)]
+struct CounterTransitionRequest {
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    #[serde(default)]
+    amount: Option<u128>, // required for "add", signed as an "Amount:" line
+    #[serde(flatten)]
+    selection: CounterSelection, // counter_id of a shared counter and co-owner approvals
+}
+
+// Request and response of the unified /execute endpoint; mirrors ExecuteRequest,
+// ExecuteResponse and CounterResult in frontend/src/types/api.ts.
+#[derive(Deserialize)]
+struct ExecuteRequest {
+    action: String, // initialize, increment, decrement, add, reset, close
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    #[serde(default)]
+    amount: Option<u128>,
+    #[serde(flatten)]
+    selection: CounterSelection,
+    // Co-owners and approval threshold of a counter being initialized
+    #[serde(default)]
+    owners: Vec<String>,
+    #[serde(default)]
+    threshold: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ExecuteResponse {
+    result: String, // JSON-encoded CounterResult
+}
+
+#[derive(Serialize)]
+struct CounterResult {
+    inputs: CounterResultInputs,
+    transaction: serde_json::Value,
+    message_to_sign: String,
+    status: String,
+    next_step: String,
+    protocol_adapter: ProtocolAdapterResult,
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CounterResultInputs {
+    action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requested_value: Option<u128>,
+    final_value: u128,
+    user_account: String,
+}
+
+#[derive(Serialize)]
+struct ProtocolAdapterResult {
+    verification: String,
+    submission: SubmissionResult,
+}
+
+#[derive(Serialize)]
+struct SubmissionResult {
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pa_contract: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CounterStateResponse {
+    counter_id: String,
+    owner: String,
+    owners: Vec<String>,
+    threshold: usize, // owners that must approve reset and close
+    value: u128,
+    commitment: String,
+    nullifier: String,
+    spent: bool, // nullifier already published by the Protocol Adapter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_in: Option<String>, // transaction that created the current resource
+    closed: bool, // commitment and nullifier are those of the final, consumed counter
+}
+
 #[derive(Serialize)]
 struct EmitTransactionResponse {
     transaction_hash: String,
@@ -71,6 +220,182 @@ struct EmitTransactionResponse {
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     transaction_data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    artifact_hash: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EmitRealTransactionResponse {
+    transaction_hash: String,
+    success: bool,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_data: Option<serde_json::Value>,
+    artifact_hash: String,
+    report: TransactionReport,
+}
+
+#[derive(Deserialize)]
+struct ResubmitArtifactRequest {
+    user_account: String, // must be the account the artifact was proved for
+    signature: String,
+    signed_message: String, // carries `Action: RESUBMIT` and `Artifact: <hash>`
+    timestamp: String,
+}
+
+#[derive(Serialize)]
+struct ResubmitArtifactResponse {
+    transaction_hash: String,
+    path: artifacts::ResubmissionPath,
+    artifact_hash: String,
+    unknown_roots: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    offset: Option<usize>,
+    limit: Option<usize>, // default 20, at most 100
+}
+
+#[derive(Serialize)]
+struct CounterHistoryResponse {
+    counter_id: String,
+    total: usize,
+    offset: usize,
+    entries: Vec<counter_store::HistoryEntry>,
+}
+
+// Request of the generic /apps endpoints. The signed message names the operation
+// (`Action: CREATE`, `TRANSFORM`, `CONSUME` or `TRANSFER`); all but create also
+// sign the commitment of the consumed resource as a `Resource:` line, and transfer
+// signs the recipient's nullifier key commitment as a `Recipient:` line.
+#[derive(Deserialize)]
+struct AppOperationRequest {
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    #[serde(default)]
+    commitment: Option<String>, // resource to consume, for transform and consume
+    #[serde(default)]
+    params: serde_json::Value, // app-specific, e.g. a counter transition
+    #[serde(default)]
+    recipient_user_key: Option<String>, // serialized UserKey, for transfer
+    #[serde(default)]
+    app_data: Vec<app_data::AppDataItem>, // attached to the created resource
+    #[serde(default)]
+    forwarder_call: Option<forwarder::ForwarderCall>, // triggered by the created resource
+}
+
+#[derive(Serialize)]
+struct AppOperationResponse {
+    transaction_hash: String,
+    artifact_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created: Option<AppResourceView>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sent: Option<SentResource>,
+}
+
+// Resource created for the recipient of a transfer. The recipient needs the full
+// resource, together with their own nullifier key, to consume it later; it is also
+// encrypted to their sepk so that it can be handed over through untrusted parties.
+#[derive(Serialize)]
+struct SentResource {
+    recipient_cnk: String,
+    view: AppResourceView,
+    resource: arm_risc0::resource::Resource,
+    ciphertext: String, // hex, ephemeral public key || IV || AES-GCM output
+}
+
+#[derive(Serialize)]
+struct AppResourceView {
+    app: String,
+    commitment: String,
+    data: serde_json::Value, // decoded by the app
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_in: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    consumed_in: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AppInfo {
+    name: String,
+    logic_ref: String,
+}
+
+#[derive(Deserialize)]
+struct InboxQuery {
+    #[serde(default)]
+    from_block: u64,
+}
+
+// A partial transaction offered as an intent, e.g. give 5 A, want 3 B. The signed
+// message carries `Action: SUBMIT_INTENT` and the lines of `intents::terms_lines`.
+#[derive(Deserialize)]
+struct SubmitIntentRequest {
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    give: intents::Asset,
+    want: intents::Asset,
+    expires_at: i64,             // unix seconds
+    partial_transaction: String, // base64 bincode ARM transaction with its delta witness
+}
+
+#[derive(Serialize)]
+struct SubmitIntentResponse {
+    intent: intents::Intent,
+    // Set when the solver matched the intent and settled the swap
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_with: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CancelIntentRequest {
+    user_account: String,
+    signature: String,
+    signed_message: String, // carries `Action: CANCEL_INTENT` and `Intent: <id>`
+    timestamp: String,
+}
+
+// Client-proved actions of one or more parties, composed into one transaction.
+#[derive(Deserialize)]
+struct ComposeRequest {
+    parts: Vec<SignedComposePart>,
+}
+
+// A part signed by the account that proved it. The signed message carries
+// `Action: COMPOSE` and `Transaction: <composition::part_hash>`.
+#[derive(Deserialize)]
+struct SignedComposePart {
+    user_account: String,
+    signature: String,
+    signed_message: String,
+    timestamp: String,
+    transaction: String, // base64 bincode ARM transaction
+}
+
+#[derive(Serialize)]
+struct ComposeResponse {
+    transaction_hash: String,
+    parties: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ComposeErrorResponse {
+    error: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    action_errors: Vec<composition::ActionError>,
+}
+
+#[derive(Deserialize)]
+struct ArtifactExportQuery {
+    format: Option<String>, // "json" (default) or "bincode"
 }
 
 
@@ -127,6 +452,7 @@ async fn emit_empty_transaction(
             success: true,
             message: "Empty transaction successfully executed on Ethereum Sepolia".to_string(),
             transaction_data: None,
+            artifact_hash: None,
         }))
         }
         Err(e) => {
@@ -142,12 +468,14 @@ async fn emit_empty_transaction(
 }
 
 async fn emit_real_transaction(
-    Json(payload): Json<EmitTransactionRequest>,
-) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    State(state): State<AppState>,
+    Json(payload): Json<EmitRealTransactionRequest>,
+) -> Result<Json<EmitRealTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
     let user_account = payload.user_account.clone();
     let signature = payload.signature.clone();
     let signed_message = payload.signed_message.clone();
     let timestamp = payload.timestamp.clone();
+    let shape = payload.shape.clone();
 
     println!("Emitting ARM transaction for account: {}", user_account);
 
@@ -161,7 +489,7 @@ async fn emit_real_transaction(
         ));
     }
 
-    // Step 2: Verify the message content
+    // Step 2: Verify the message content and the requested shape
     if let Err(e) = verify_message_content(&signed_message, "emit_transaction", &user_account, &timestamp) {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -170,75 +498,140 @@ async fn emit_real_transaction(
             }),
         ));
     }
+    if let Err(e) = shape.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Unsupported transaction shape: {}", e),
+            }),
+        ));
+    }
     
     println!("Signature and message verified. Generating real ARM transaction...");
 
-    // Step 3: Generate ARM transaction with manual proof correction workaround
-    println!("Generating ARM transaction with 1 action...");
+    // Step 3: Fetch the verifier selector the Protocol Adapter routes proofs to
+    let verifier_selector = verification::get_verifier_selector(&protocol_adapter()).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Step 4: Generate ARM transaction and verify it locally before paying gas
+    println!("Generating ARM transaction with {} actions...", shape.actions);
 
-    let real_tx = tokio::task::spawn_blocking(|| {
-        let raw_tx: ArmTransaction = transaction::generate_test_transaction(1);
-        
-        println!("Generated ARM transaction with {} actions", raw_tx.actions.len());
+    let artifacts = state.artifacts.clone();
+    let artifact_account = user_account.clone();
+    let gas_limit = shape.gas_limit();
+    let (real_tx, artifact_hash, mut report) = tokio::task::spawn_blocking(move || {
+        let started = std::time::Instant::now();
+        let raw_tx: ArmTransaction = shape.generate()?;
+        let proving_time = started.elapsed();
         
+        println!("Generated ARM transaction with {} actions in {:?}", raw_tx.actions.len(), proving_time);
         
         // Convert to EVM Protocol Adapter format
-        let evm_tx = ProtocolAdapter::Transaction::from(raw_tx);
-        
-        // // WORKAROUND: Manual proof correction due to ProtocolAdapter conversion corruption
-        // // The ProtocolAdapter::Transaction::from() conversion corrupts proof data during ARM->EVM format conversion
-        // // This is a temporary fix until the root cause in the bindings is resolved
-        // println!("Applying proof correction workaround...");
-
-        // // 1. Define the correct proof hex strings (without the "0x" prefix for parsing)
-        // let good_logic_proof_1 = "bb001d441180d22565f9ef5b2936543dd3db4933cf81bc11544f85f1d9b6bf6165267551119d9ff3b5fbbaf8e1fbb697dda7d161c06d3833a0b7d1fe9d2eededc7613740266b9d9933503801208dedfbd3d2f28ada393ac52691d5bcdc37fe621c3840fc057dd54a27bce5c1acdc9a6cdf48d88a00b41e2bafc7f09e098448811c3ce4e72f74b34b8a778ed50b7b102ecf5482e4f61b2c4a8f48fd9d759e7a26b5028abb1a7812d56029cf9b7bbc2360893bbdea37f7f952e89a07939e70cc35d478b3570f8d372c642baf065d0afa3b62d92b10044ceb91c9eb7b4e98df335086972cf425afff190d3c47b7f8716104a54457eae1253575857b63af3fc64c34a7d28f98";
-        // let good_logic_proof_2 = "bb001d442fe46a111e5609cd74ef5748d74f740495a0efbbe7b683b84233750ef0adc2b21006062e2bd35d9c0dd052fb20084d60046a2d48c04711d85d1f712cc6de7b95117b41b0a2f7500ae2ad7d6630ee1eab9e1b9f9739522004408961c3cfaa374f01d09c9afeeb981371a1a37d99c7fb2fa3fe1ddd0f5fe28abc200dfd84c9ae03023af4c3ba40775768ed9433ca8275c34d13c2c23e3cd6dd5f2ce67cf68972a70bd1391b70faf111bfc48b88bcd50e028eb4206920e23bd9eab31460fbdb9dfd1edaceedaf8d628b47dbc047b5b85ed7e28c67df0b100fc2d9b26d3f4bb136332c4ef0885db68658f933a16c7d46ec04ed6d936fe9deb092b131362a6fdc6d30";
-        // let good_compliance_proof = "bb001d442e1936cbe1ed65e5a9fe49d4415ee69d08214786d0d25f9e538876e400b0259c11b2fd53b46d7c1d7fba037dfa45019b3a52b58eeb7233db6a8c73006c0c2dc30592fcc1104ad2bf634f67b2984840c2b77e45d82b5b69bf1c7c9c705916dc3e184f55c87ab32069be899f7f2de86fe61d77fbc378f1c8d97c58b5bc4d72d6e529d14fb61390ee7e57fa1e0765203137df744675d378e5f1e32f1843a2833d750eec0183ebe74dadbfcd526fdfca658fd31a7b15d98d638363e9036fc1d6cd46283d7f0477111efc466a61c5e37c1b0af4849f9720e584172192376ac5f74d5a11f579ea3f083748d4bf8786ee64b4dcb9fb18c633c83fd0e2f3afcb4fe23b79";
-
-        // // Apply the corrected proofs
-        // evm_tx.actions[0].logicVerifierInputs[0].proof = hex::decode(good_logic_proof_1).unwrap().into();
-        // evm_tx.actions[0].logicVerifierInputs[1].proof = hex::decode(good_logic_proof_2).unwrap().into();
-        // evm_tx.actions[0].complianceVerifierInputs[0].proof = hex::decode(good_compliance_proof).unwrap().into();
-        
-        println!("Proof correction applied successfully.");
+        let evm_tx = ProtocolAdapter::Transaction::from(raw_tx.clone());
+
+        // The conversion has corrupted proofs before, so check both forms locally
+        verification::verify_transaction(&raw_tx, &evm_tx, &ExpectedImageIds::any_logic(), Some(verifier_selector))?;
 
-        evm_tx
+        // Keep the proved transaction so a failed submission can be retried
+        let artifact_hash = artifacts.save(
+            &ProvingInputs::Test { actions: shape.actions },
+            Some(&artifact_account),
+            &raw_tx,
+            &evm_tx,
+        )?;
+
+        let report = TransactionReport::measure(&evm_tx, proving_time);
+        Ok::<_, String>((evm_tx, artifact_hash, report))
     }).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
-                error: format!("Failed to generate/fix ARM transaction: {}", e),
+                error: format!("Failed to generate ARM transaction: {}", e),
+            }),
+        )
+    })?.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Local verification failed, transaction not submitted: {}", e),
             }),
         )
     })?;
 
-    // The rest of your function remains the same...
+    // Step 5: Submit and wait for the receipt, which reports the gas used
     let adapter = protocol_adapter();
 
-    match adapter.execute(real_tx.clone()).gas(3_000_000u64).send().await {
-        Ok(pending_tx) => {
-            let tx_hash = pending_tx.tx_hash();
-            println!("Real ARM transaction confirmed! Hash: 0x{}", hex::encode(tx_hash));
+    let result = async {
+        let pending_tx = adapter
+            .execute(real_tx.clone())
+            .gas(gas_limit)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to submit: {}", e))?;
+        let receipt = pending_tx
+            .get_receipt()
+            .await
+            .map_err(|e| format!("Failed to get receipt: {}", e))?;
+        Ok::<_, String>(receipt)
+    }
+    .await;
+
+    match result {
+        Ok(receipt) => {
+            let tx_hash = format!("0x{}", hex::encode(receipt.transaction_hash()));
+            report.gas_used = Some(receipt.gas_used());
+            if !receipt.status() {
+                println!("Real ARM transaction {} reverted", tx_hash);
+                record_submission(&state.artifacts, &artifact_hash, ArtifactStatus::Failed {
+                    error: format!("Transaction {} reverted", tx_hash),
+                });
+                return Ok(Json(EmitRealTransactionResponse {
+                    transaction_hash: tx_hash,
+                    success: false,
+                    message: "Real ARM transaction reverted".to_string(),
+                    transaction_data: None,
+                    artifact_hash,
+                    report,
+                }));
+            }
+
+            println!("Real ARM transaction confirmed! Hash: {} ({} gas)", tx_hash, receipt.gas_used());
+            record_submission(&state.artifacts, &artifact_hash, ArtifactStatus::Submitted {
+                tx_hash: tx_hash.clone(),
+            });
             
-            return Ok(Json(EmitTransactionResponse {
-                transaction_hash: format!("0x{}", hex::encode(tx_hash)),
+            Ok(Json(EmitRealTransactionResponse {
+                transaction_hash: tx_hash,
                 success: true,
                 message: "Real ARM transaction successfully executed via Alloy backend".to_string(),
                 transaction_data: None,
-            }));
+                artifact_hash,
+                report,
+            }))
         }
         Err(e) => {
-            println!("Failed to submit real ARM transaction: {:?}", e);
+            println!("Failed to submit real ARM transaction: {}", e);
+            record_submission(&state.artifacts, &artifact_hash, ArtifactStatus::Failed {
+                error: e.clone(),
+            });
             
             // This fallback path should ideally not be hit anymore.
             let transaction_data = serde_json::to_value(&real_tx).ok();
             
-            return Ok(Json(EmitTransactionResponse {
+            Ok(Json(EmitRealTransactionResponse {
                 transaction_hash: "".to_string(),
                 success: false,
                 message: format!("Alloy backend failed: {}. Transaction data provided for ethers.js frontend.", e),
                 transaction_data,
-            }));
+                artifact_hash,
+                report,
+            }))
         }
     }
 }
@@ -274,80 +667,15 @@ async fn emit_counter_transaction(
         ));
     }
     
-    // Step 3: Get the latest root from Protocol Adapter before initialization
-    let init_latest_root = {
-        let adapter = protocol_adapter();
-        match adapter.latestRoot().call().await {
-            Ok(root) => {
-                println!("Protocol Adapter latest root before initialization: 0x{}", hex::encode(&root));
-                root
-            }
-            Err(e) => {
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: format!("Failed to get latest root before initialization: {}", e),
-                    }),
-                ));
-            }
-        }
-    };
-    
-    let arm_tx = tokio::task::spawn_blocking(move || {
-        // Use the actual ARM counter application logic!
-        let (tx, resource, nf_key) = app::init::create_init_counter_tx();
-        
-        // Convert ARM transaction to EVM Protocol Adapter format
-        let evm_tx = ProtocolAdapter::Transaction::from(tx);
-
-        println!("initialize counter evm_tx: {:?}", evm_tx);
-        println!("Protocol Adapter root before init: 0x{}", hex::encode(&init_latest_root));
-        
-        (evm_tx, resource, nf_key)
-    }).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Failed to generate ARM counter transaction: {}", e),
-            }),
-        )
-    })?;
-
-    let (evm_tx, resource, nf_key) = arm_tx;
+    let outcome = run_counter_init(&state, &user_account, Ownership::single(&user_account)).await?;
 
-    // Step 4: Submit to Protocol Adapter
-    let adapter = protocol_adapter();
-    match adapter.execute(evm_tx).send().await {
-        Ok(pending_tx) => {
-            let tx_hash = pending_tx.tx_hash();
-            println!("ARM counter transaction confirmed! Hash: 0x{}", hex::encode(tx_hash));
-            
-            // Store the counter resource and nullifier key for future increment operations
-            {
-                let mut store = state.counter_store.lock().unwrap();
-                store.insert(user_account.clone(), (resource.clone(), nf_key.clone()));
-                println!("Stored counter state for user: {}", user_account);
-                println!("Stored resource commitment: 0x{}", hex::encode(resource.commitment()));
-                println!("Stored nullifier key: [NullifierKey debug info]");
-            }
-            
-            Ok(Json(EmitTransactionResponse {
-                transaction_hash: format!("0x{}", hex::encode(tx_hash)),
-                success: true,
-                message: "ARM counter initialization transaction with ZK proofs successfully executed on Ethereum Sepolia".to_string(),
-                transaction_data: None,
-            }))
-        }
-        Err(e) => {
-            println!("Failed to submit ARM counter transaction: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Failed to submit transaction: {}", e),
-                }),
-            ))
-        }
-    }
+    Ok(Json(EmitTransactionResponse {
+        transaction_hash: outcome.transaction_hash,
+        success: true,
+        message: "ARM counter initialization transaction with ZK proofs successfully executed on Ethereum Sepolia".to_string(),
+        transaction_data: None,
+        artifact_hash: Some(outcome.artifact_hash),
+    }))
 }
 
 async fn emit_increment_transaction(
@@ -381,175 +709,1668 @@ async fn emit_increment_transaction(
         ));
     }
     
-    // Step 3: Get the stored counter state for this user
-    let (counter_resource, counter_nf_key) = {
-        let store = state.counter_store.lock().unwrap();
-        match store.get(&user_account) {
-            Some((resource, nf_key)) => (resource.clone(), nf_key.clone()),
-            None => {
-                return Err((
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: "Counter not initialized for this user. Please run the counter initialization transaction first.".to_string(),
-                    }),
-                ));
-            }
-        }
-    };
-
-    println!("Retrieved stored counter state for user: {}", user_account);
-    println!("Current counter value: {}", u128::from_le_bytes(counter_resource.value_ref[0..16].try_into().unwrap_or([0; 16])));
-    // println!("Retrieved resource commitment: 0x{}", hex::encode(counter_resource.nk_commitment.inner()));
-    println!("Retrieved nullifier key: [NullifierKey debug info]");
-    
-    // Step 3.5: Get the latest root from the Protocol Adapter
-    // let latest_root = {
-    //     let adapter = protocol_adapter();
-    //     println!("Getting latest root from Protocol Adapter...");
-        
-    //     match adapter.latestRoot().call().await {
-    //         Ok(root) => {
-    //             println!("Successfully retrieved latest root: 0x{}", hex::encode(&root));
-    //             root
-    //         }
-    //         Err(e) => {
-    //             return Err((
-    //                 StatusCode::INTERNAL_SERVER_ERROR,
-    //                 Json(ErrorResponse {
-    //                     error: format!("Failed to get latest root from Protocol Adapter: {}", e),
-    //                 }),
-    //             ));
-    //         }
-    //     }
-    // };
-
-    let (latest_root, merkle_path) = {
-        let adapter = protocol_adapter();
-        
-        // First, get the latest root from the Protocol Adapter
-        let latest_root = match adapter.latestRoot().call().await {
-            Ok(root) => {
-                println!("Protocol Adapter latest root: 0x{}", hex::encode(&root));
-                root
-            }
-            Err(e) => {
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: format!("Failed to get latest root from Protocol Adapter: {}", e),
-                    }),
-                ));
-            }
-        };
-        
-        // Then get the merkle proof for the stored resource
-        let commitment_b256 = alloy::primitives::B256::from_slice(counter_resource.commitment().as_bytes());
-        println!("Getting merkle proof for commitment: 0x{}", hex::encode(commitment_b256));
+    // Step 3: Prove and submit the increment of the caller's active counter
+    let outcome = run_counter_transition(
+        &state,
+        &user_account,
+        &signed_message,
+        &CounterSelection::default(),
+        CounterTransition::Increment,
+    )
+    .await?;
 
-        // Get the merkle proof - this will give us the proof for whatever root contains this commitment
-        let path = get_merkle_path(&adapter, commitment_b256).await.map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Failed to get merkle proof for commitment: {}", e),
-                }),
-            )
-        })?;
+    Ok(Json(EmitTransactionResponse {
+        transaction_hash: outcome.transaction_hash,
+        success: true,
+        message: "ARM counter increment transaction with ZK proofs successfully executed on Ethereum Sepolia".to_string(),
+        transaction_data: None,
+        artifact_hash: Some(outcome.artifact_hash),
+    }))
+}
 
-        println!("Successfully retrieved Merkle path from Protocol Adapter");
-        
-        (latest_root, path)
-    };
+// Result of a counter operation that was proved, verified and executed.
+struct CounterOutcome {
+    transaction_hash: String,
+    previous_value: Option<u128>, // None for initialization
+    new_value: u128,
+    artifact_hash: String,
+    evm_transaction: serde_json::Value,
+}
 
-    let arm_tx = tokio::task::spawn_blocking(move || {
-        // Use a custom increment function that uses the merkle path from Protocol Adapter
-        println!("Creating increment transaction with merkle path from Protocol Adapter...");
-        println!("Protocol Adapter current root: 0x{}", hex::encode(&latest_root));
-        
-        // Create increment transaction with proper merkle path 
-        let (tx, new_resource) = create_increment_tx_with_merkle_path(counter_resource, counter_nf_key.clone(), merkle_path);
-        
-        println!("Increment transaction created successfully");
-        println!("New counter value: {}", u128::from_le_bytes(new_resource.value_ref[0..16].try_into().unwrap_or([0; 16])));
-        
-        // Convert ARM transaction to EVM Protocol Adapter format first (before modifying anything)
-        let evm_tx = ProtocolAdapter::Transaction::from(tx);
-        println!("increment evm_tx: {:?}", evm_tx);
-        println!("Converted ARM transaction to EVM format");
-        
-        // Compare ARM's calculated root with Protocol Adapter's latest root
-        if !evm_tx.actions.is_empty() && !evm_tx.actions[0].complianceVerifierInputs.is_empty() {
-            let arm_calculated_root = &evm_tx.actions[0].complianceVerifierInputs[0].instance.consumed.commitmentTreeRoot;
-            println!("ARM calculated root: 0x{}", hex::encode(arm_calculated_root));
-            println!("Protocol Adapter root: 0x{}", hex::encode(&latest_root));
-            println!("Roots match: {}", arm_calculated_root == latest_root.as_slice());
-        }
-        
-        // The new_resource from increment already has the correct state, but we need to extract
-        // or create a nullifier key for future use. However, we can't modify the resource after
-        // the transaction is created as it would invalidate the proofs.
-        // For now, let's use the old nullifier key pattern - in a real implementation,
-        // the nullifier key for the new resource should be generated within the increment function.
-        
-        // Debug: Show nullifiers and commitments
-        if !evm_tx.actions.is_empty() && !evm_tx.actions[0].complianceVerifierInputs.is_empty() {
-            let compliance_input = &evm_tx.actions[0].complianceVerifierInputs[0];
-            println!("Increment transaction nullifier: 0x{}", hex::encode(&compliance_input.instance.consumed.nullifier));
-            println!("Increment transaction new commitment: 0x{}", hex::encode(&compliance_input.instance.created.commitment));
-        }
-        
-        // Optional: Log transaction structure for debugging
-        if std::env::var("DEBUG_TRANSACTIONS").is_ok() {
-            match serde_json::to_string_pretty(&evm_tx) {
-                Ok(json) => println!("EVM Increment Transaction JSON:\n{}", json),
-                Err(e) => println!("Failed to serialize EVM increment transaction: {}", e),
-            }
-        }
-        
-        // For now, let's not store the updated state to avoid chain corruption
-        // This means only one increment will work, but let's see if that works first
-        (evm_tx, new_resource, counter_nf_key)
-    }).await.map_err(|e| {
+// Proves, verifies, stores and submits a counter operation with the registered
+// counter app, like the generic /apps endpoints do.
+async fn run_counter_operation(
+    state: &AppState,
+    user_account: &str,
+    operation: AppOperation,
+) -> Result<AppReceipt, (StatusCode, Json<ErrorResponse>)> {
+    let internal_error = |error: String| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
-                error: format!("Failed to generate ARM increment transaction: {}", e),
+                error,
             }),
         )
-    })?;
-
-    let (evm_tx, new_resource, counter_nf_key) = arm_tx;
-
-    // Step 4: Submit to Protocol Adapter
+    };
+    let app = state.registry.get(counter::COUNTER_APP).map_err(internal_error)?;
+    let action = operation.name();
+    let receipt = registry::run(app, state.artifacts.clone(), user_account, operation)
+        .await
+        .map_err(internal_error)?;
+    println!("ARM counter {} transaction submitted: {}", action, receipt.tx_hash);
+    Ok(receipt)
+}
+
+// The resource a counter operation created; create, transform and consume all
+// create one.
+fn created_counter(receipt: &AppReceipt) -> Result<AppResource, (StatusCode, Json<ErrorResponse>)> {
+    receipt.created.clone().ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Counter transaction {} created no resource", receipt.tx_hash),
+            }),
+        )
+    })
+}
+
+// Proves, verifies and submits a counter initialization, then stores the new counter
+// resource and its nullifier key for the caller.
+async fn run_counter_init(
+    state: &AppState,
+    user_account: &str,
+    ownership: Ownership,
+) -> Result<CounterOutcome, (StatusCode, Json<ErrorResponse>)> {
+    let operation = AppOperation::Create {
+        params: serde_json::Value::Null.to_string(),
+        app_data: vec![],
+        forwarder_call: None,
+    };
+    let receipt = run_counter_operation(state, user_account, operation).await?;
+    let created = created_counter(&receipt)?;
+
+    // Store the counter resource and nullifier key for future operations
+    let new_value = counter::counter_value(&created.resource);
+    {
+        let mut store = state.counter_store.lock().unwrap();
+        store.insert_new(user_account, ownership, created.resource, created.nf_key, Some(receipt.tx_hash.clone()));
+        println!("Stored counter state for user: {}", user_account);
+    }
+
+    Ok(CounterOutcome {
+        transaction_hash: receipt.tx_hash,
+        previous_value: None,
+        new_value,
+        artifact_hash: receipt.artifact_hash,
+        evm_transaction: serde_json::to_value(&receipt.evm_tx).unwrap_or(serde_json::Value::Null),
+    })
+}
+
+// Looks up the counter a signed request acts on and checks that the signer may run
+// `action` on it, including co-owner approvals for destructive actions. The returned
+// guard holds the counter's lock; keep it until the store is updated.
+async fn select_counter(
+    state: &AppState,
+    user_account: &str,
+    signed_message: &str,
+    selection: &CounterSelection,
+    action: &str,
+) -> Result<(String, Resource, NullifierKey, tokio::sync::OwnedMutexGuard<()>), (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error,
+            }),
+        )
+    };
+
+    let counter_id = {
+        let store = state.counter_store.lock().unwrap();
+        store.select(user_account, selection.counter_id.as_deref()).map_err(bad_request)?.counter_id.clone()
+    };
+    // Another operation may have advanced or closed the counter while we waited for
+    // the lock, so it is read again under the lock
+    let guard = state.counter_locks.lock(&counter_id).await;
+    let store = state.counter_store.lock().unwrap();
+    let record = store.select(user_account, Some(&counter_id)).map_err(bad_request)?;
+
+    ownership::authorize(
+        &record.ownership,
+        &record.counter_id,
+        action,
+        user_account,
+        signed_message,
+        &selection.approvals,
+    ).map_err(|e| {
+        (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: format!("Authorization failed: {}", e),
+            }),
+        )
+    })?;
+
+    let nf_key = record.nf_key.clone().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Counter {} has no nullifier key", record.counter_id),
+            }),
+        )
+    })?;
+    Ok((record.counter_id.clone(), record.resource.clone(), nf_key, guard))
+}
+
+// Proves, verifies and submits a transition of a stored counter, then stores the new
+// counter resource. The caller has already checked the signed intent; ownership and
+// approvals are checked here.
+async fn run_counter_transition(
+    state: &AppState,
+    user_account: &str,
+    signed_message: &str,
+    selection: &CounterSelection,
+    transition: CounterTransition,
+) -> Result<CounterOutcome, (StatusCode, Json<ErrorResponse>)> {
+    // Step 1: Get the stored counter state and check the caller may transition it
+    let (counter_id, counter_resource, counter_nf_key, _counter_guard) =
+        select_counter(state, user_account, signed_message, selection, transition.action_name()).await?;
+
+    // Reject invalid transitions (e.g. decrementing 0) before spending time proving
+    let previous_value = counter::counter_value(&counter_resource);
+    let new_value = transition.apply(previous_value).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Step 2: Prove, verify and submit the transition
+    let consumed = counter_resource.clone();
+    let operation = AppOperation::Transform {
+        consumed: AppResource {
+            resource: counter_resource,
+            nf_key: counter_nf_key,
+        },
+        params: serde_json::json!(transition).to_string(),
+        app_data: vec![],
+        forwarder_call: None,
+    };
+    let receipt = run_counter_operation(state, user_account, operation).await?;
+    let created = created_counter(&receipt)?;
+
+    // Step 3: Move the stored counter to the new resource
+    {
+        let mut store = state.counter_store.lock().unwrap();
+        if let Err(e) = store.advance(&counter_id, &consumed, created.resource, transition.action_name(), user_account, Some(receipt.tx_hash.clone())) {
+            println!("Failed to update counter state: {}", e);
+        }
+        println!("Updated counter state for user: {}", user_account);
+    }
+
+    Ok(CounterOutcome {
+        transaction_hash: receipt.tx_hash,
+        previous_value: Some(previous_value),
+        new_value,
+        artifact_hash: receipt.artifact_hash,
+        evm_transaction: serde_json::to_value(&receipt.evm_tx).unwrap_or(serde_json::Value::Null),
+    })
+}
+
+// Proves, verifies and submits the closing of a stored counter, then tombstones it
+// in the store. The caller has already checked the signed message; ownership and
+// approvals are checked here.
+async fn run_counter_close(
+    state: &AppState,
+    user_account: &str,
+    signed_message: &str,
+    selection: &CounterSelection,
+) -> Result<CounterOutcome, (StatusCode, Json<ErrorResponse>)> {
+    // Step 1: Get the stored counter state and check the caller may close it
+    let (counter_id, counter_resource, counter_nf_key, _counter_guard) =
+        select_counter(state, user_account, signed_message, selection, "close").await?;
+    let final_value = counter::counter_value(&counter_resource);
+
+    // Step 2: Prove, verify and submit the closing action
+    let operation = AppOperation::Consume {
+        consumed: AppResource {
+            resource: counter_resource,
+            nf_key: counter_nf_key,
+        },
+    };
+    let receipt = run_counter_operation(state, user_account, operation).await?;
+    let ephemeral = created_counter(&receipt)?;
+
+    // Step 3: Tombstone the stored counter
+    {
+        let mut store = state.counter_store.lock().unwrap();
+        if let Err(e) = store.close(&counter_id, ephemeral.resource, user_account, Some(receipt.tx_hash.clone())) {
+            println!("Failed to close counter state: {}", e);
+        }
+        println!("Closed counter {} for user: {}", counter_id, user_account);
+    }
+
+    Ok(CounterOutcome {
+        transaction_hash: receipt.tx_hash,
+        previous_value: Some(final_value),
+        new_value: final_value,
+        artifact_hash: receipt.artifact_hash,
+        evm_transaction: serde_json::to_value(&receipt.evm_tx).unwrap_or(serde_json::Value::Null),
+    })
+}
+
+async fn emit_counter_transition(
+    state: AppState,
+    action: &str,
+    payload: CounterTransitionRequest,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+
+    println!("Emitting ARM counter {} transaction for account: {}", action, user_account);
+
+    let intent = CounterIntent::new(action, payload.amount).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content, including the signed amount
+    if let Err(e) = verify_message_content(&payload.signed_message, action, &user_account, &payload.timestamp)
+        .and_then(|_| intent.verify_message(&payload.signed_message))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Message verification failed: {}", e),
+            }),
+        ));
+    }
+
+    let outcome = run_counter_transition(
+        &state,
+        &user_account,
+        &payload.signed_message,
+        &payload.selection,
+        intent.transition,
+    ).await?;
+
+    Ok(Json(EmitTransactionResponse {
+        transaction_hash: outcome.transaction_hash,
+        success: true,
+        message: format!(
+            "ARM counter {} transaction executed: {} -> {}",
+            action, outcome.previous_value.unwrap_or_default(), outcome.new_value
+        ),
+        transaction_data: None,
+        artifact_hash: Some(outcome.artifact_hash),
+    }))
+}
+
+async fn execute_counter_action(
+    State(state): State<AppState>,
+    Json(payload): Json<ExecuteRequest>,
+) -> Result<Json<ExecuteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+    let action = payload.action.to_lowercase();
+
+    println!("Executing counter action '{}' for account: {}", action, user_account);
+
+    // Initialization and closing have no transition; everything else is a signed
+    // counter intent
+    let intent = if action == "initialize" || action == "close" {
+        if payload.amount.is_some() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("The {} action does not take an amount", action),
+                }),
+            ));
+        }
+        None
+    } else {
+        Some(CounterIntent::new(&action, payload.amount).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: e,
+                }),
+            )
+        })?)
+    };
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content
+    let message_check = verify_message_content(&payload.signed_message, &action, &user_account, &payload.timestamp)
+        .and_then(|_| match &intent {
+            Some(intent) => intent.verify_message(&payload.signed_message),
+            None => Ok(()),
+        });
+    if let Err(e) = message_check {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Message verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 3: Run the matching ARM counter operation
+    let outcome = match &intent {
+        None if action == "close" => {
+            run_counter_close(&state, &user_account, &payload.signed_message, &payload.selection).await?
+        }
+        None => {
+            let ownership = Ownership::new(&user_account, payload.owners.clone(), payload.threshold).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        error: e,
+                    }),
+                )
+            })?;
+            run_counter_init(&state, &user_account, ownership).await?
+        }
+        Some(intent) => {
+            run_counter_transition(&state, &user_account, &payload.signed_message, &payload.selection, intent.transition)
+                .await?
+        }
+    };
+
     let adapter = protocol_adapter();
-    match adapter.execute(evm_tx).send().await {
-        Ok(pending_tx) => {
-            let tx_hash = pending_tx.tx_hash();
-            println!("ARM increment transaction confirmed! Hash: 0x{}", hex::encode(tx_hash));
-            
-            // Update the stored counter state with the new resource and nullifier key
-            {
-                let mut store = state.counter_store.lock().unwrap();
-                store.insert(user_account.clone(), (new_resource, counter_nf_key));
-                println!("Updated counter state for user: {}", user_account);
-            }
-            
-            Ok(Json(EmitTransactionResponse {
-                transaction_hash: format!("0x{}", hex::encode(tx_hash)),
-                success: true,
-                message: "ARM counter increment transaction with ZK proofs successfully executed on Ethereum Sepolia".to_string(),
-                transaction_data: None,
+    let chain_id = adapter.provider().get_chain_id().await.ok();
+
+    let result = CounterResult {
+        inputs: CounterResultInputs {
+            action: action.clone(),
+            requested_value: payload.amount,
+            final_value: outcome.new_value,
+            user_account: user_account.clone(),
+        },
+        transaction: outcome.evm_transaction,
+        message_to_sign: counter::signing_message(&action, &user_account, &payload.timestamp),
+        status: "executed".to_string(),
+        next_step: "Counter updated on-chain. Sign a new message to run the next action.".to_string(),
+        protocol_adapter: ProtocolAdapterResult {
+            verification: "Compliance, logic and delta proofs verified locally before submission".to_string(),
+            submission: SubmissionResult {
+                status: "submitted".to_string(),
+                tx_hash: Some(outcome.transaction_hash),
+                pa_contract: Some(format!("{}", adapter.address())),
+                chain_id,
+                error: None,
+            },
+        },
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        message: Some(match outcome.previous_value {
+            _ if action == "close" => format!("Counter closed at {}", outcome.new_value),
+            Some(previous) => format!("Counter {}: {} -> {}", action, previous, outcome.new_value),
+            None => format!("Counter initialized at {}", outcome.new_value),
+        }),
+    };
+
+    let result = serde_json::to_string(&result).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to serialize counter result: {}", e),
+            }),
+        )
+    })?;
+
+    Ok(Json(ExecuteResponse { result }))
+}
+
+async fn emit_close_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<CounterTransitionRequest>,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+
+    println!("Emitting ARM counter close transaction for account: {}", user_account);
+
+    if payload.amount.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "The close action does not take an amount".to_string(),
+            }),
+        ));
+    }
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content
+    if let Err(e) = verify_message_content(&payload.signed_message, "close", &user_account, &payload.timestamp) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Message verification failed: {}", e),
+            }),
+        ));
+    }
+
+    let outcome = run_counter_close(&state, &user_account, &payload.signed_message, &payload.selection).await?;
+
+    Ok(Json(EmitTransactionResponse {
+        transaction_hash: outcome.transaction_hash,
+        success: true,
+        message: format!("ARM counter closed at value {}", outcome.new_value),
+        transaction_data: None,
+        artifact_hash: Some(outcome.artifact_hash),
+    }))
+}
+
+async fn emit_decrement_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<CounterTransitionRequest>,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    emit_counter_transition(state, "decrement", payload).await
+}
+
+async fn emit_add_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<CounterTransitionRequest>,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    emit_counter_transition(state, "add", payload).await
+}
+
+async fn emit_reset_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<CounterTransitionRequest>,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    emit_counter_transition(state, "reset", payload).await
+}
+
+async fn emit_batched_increment_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<EmitTransactionRequest>,
+) -> Result<Json<EmitTransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+    let signature = payload.signature.clone();
+    let signed_message = payload.signed_message.clone();
+    let timestamp = payload.timestamp.clone();
+
+    println!("Queueing batched ARM increment for account: {}", user_account);
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &signed_message, &signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content
+    if let Err(e) = verify_message_content(&signed_message, "emit_transaction", &user_account, &timestamp) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Message verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 3: Get the stored counter state for this user, holding the counter's lock
+    // until the store records the increment
+    let (counter_id, counter_resource, counter_nf_key, _counter_guard) =
+        select_counter(&state, &user_account, &signed_message, &CounterSelection::default(), "increment").await?;
+
+    if state.batcher.is_pending(&counter_id) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "An increment for this counter is already waiting in a batch".to_string(),
+            }),
+        ));
+    }
+
+    // Step 4: Get the merkle path of the stored counter and prove the action
+    let adapter = protocol_adapter();
+    let commitment_b256 = alloy::primitives::B256::from_slice(counter_resource.commitment().as_bytes());
+    let merkle_path = get_merkle_path(&adapter, commitment_b256).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to get merkle proof for commitment: {}", e),
+            }),
+        )
+    })?;
+
+    let job_account = user_account.clone();
+    let job_counter_id = counter_id.clone();
+    let job = tokio::task::spawn_blocking(move || {
+        let (action, delta_witness, new_resource) =
+            create_increment_action_with_merkle_path(counter_resource.clone(), counter_nf_key.clone(), merkle_path);
+        BatchJob {
+            counter_id: job_counter_id,
+            increment: BatchedIncrement {
+                user_account: job_account,
+                consumed: counter_resource,
+                created: new_resource,
+                nf_key: counter_nf_key,
+            },
+            action,
+            delta_witness,
+        }
+    }).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to generate ARM increment action: {}", e),
+            }),
+        )
+    })?;
+    let consumed = job.increment.consumed.clone();
+    let new_resource = job.increment.created.clone();
+
+    // Step 5: Wait for the batch containing this action to be executed
+    let receipt = state.batcher.submit(job).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Batched increment failed: {}", e),
+            }),
+        )
+    })?;
+
+    {
+        let mut store = state.counter_store.lock().unwrap();
+        if let Err(e) = store.advance(&counter_id, &consumed, new_resource, "increment", &user_account, Some(receipt.tx_hash.clone())) {
+            println!("Failed to update counter state: {}", e);
+        }
+        println!("Updated counter state for user: {}", user_account);
+    }
+
+    let message = if receipt.isolated {
+        "Batch was rejected; ARM counter increment was executed in its own transaction".to_string()
+    } else {
+        format!("ARM counter increment executed in a batch of {} actions", receipt.batch_size)
+    };
+
+    Ok(Json(EmitTransactionResponse {
+        transaction_hash: receipt.tx_hash,
+        success: true,
+        message,
+        transaction_data: None,
+        artifact_hash: Some(receipt.artifact_hash),
+    }))
+}
+
+// Decodes a stored counter and asks the Protocol Adapter whether its current
+// resource has already been consumed.
+async fn counter_state(record: counter_store::CounterRecord) -> Result<CounterStateResponse, String> {
+    let (value, commitment, nullifier) = match &record.nf_key {
+        Some(nf_key) => {
+            let state = counter::CounterState::decode(&record.resource, nf_key)?;
+            (state.value, digest_hex(&state.commitment), digest_hex(&state.nullifier))
+        }
+        // The nullifier key of a closed or transferred counter is gone; its last entry
+        // records the final counter resource
+        None => {
+            let closing = record
+                .history
+                .last()
+                .filter(|entry| matches!(entry.transition.as_str(), "close" | "transfer"))
+                .ok_or_else(|| format!("Counter {} has no closing history entry", record.counter_id))?;
+            (
+                closing.value,
+                closing.previous_commitment.clone().unwrap_or_default(),
+                closing.nullifier.clone().unwrap_or_default(),
+            )
+        }
+    };
+    let nullifier_b256: alloy::primitives::B256 = nullifier
+        .parse()
+        .map_err(|e| format!("Invalid nullifier {}: {}", nullifier, e))?;
+    let spent = protocol_adapter()
+        .contains(nullifier_b256)
+        .call()
+        .await
+        .map_err(|e| format!("Failed to call contains: {}", e))?;
+
+    Ok(CounterStateResponse {
+        counter_id: record.counter_id,
+        owner: record.owner,
+        owners: record.ownership.owners,
+        threshold: record.ownership.threshold,
+        value,
+        commitment,
+        nullifier,
+        spent,
+        created_in: record.created_in,
+        closed: record.closed,
+    })
+}
+
+fn digest_hex(digest: &Digest) -> String {
+    format!("0x{}", hex::encode(digest.as_bytes()))
+}
+
+async fn get_account_counters(
+    State(state): State<AppState>,
+    Path(account): Path<String>,
+) -> Result<Json<Vec<CounterStateResponse>>, (StatusCode, Json<ErrorResponse>)> {
+    let records: Vec<_> = {
+        let store = state.counter_store.lock().unwrap();
+        store.for_account(&account).into_iter().cloned().collect()
+    };
+
+    let mut counters = vec![];
+    for record in records {
+        counters.push(counter_state(record).await.map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e,
+                }),
+            )
+        })?);
+    }
+    Ok(Json(counters))
+}
+
+fn find_counter(
+    state: &AppState,
+    account: &str,
+    counter_id: &str,
+) -> Result<counter_store::CounterRecord, (StatusCode, Json<ErrorResponse>)> {
+    let store = state.counter_store.lock().unwrap();
+    store
+        .get(counter_id)
+        .filter(|record| record.ownership.is_owner(account))
+        .cloned()
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("No counter {} for account {}", counter_id, account),
+                }),
+            )
+        })
+}
+
+async fn get_counter(
+    State(state): State<AppState>,
+    Path((account, counter_id)): Path<(String, String)>,
+) -> Result<Json<CounterStateResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let record = find_counter(&state, &account, &counter_id)?;
+
+    counter_state(record).await.map(Json).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })
+}
+
+async fn get_counter_history(
+    State(state): State<AppState>,
+    Path((account, counter_id)): Path<(String, String)>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<CounterHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let record = find_counter(&state, &account, &counter_id)?;
+    let total = record.history.len();
+    let offset = query.offset.unwrap_or(0).min(total);
+    let limit = query.limit.unwrap_or(20).min(100);
+    let mut entries: Vec<_> = record.history.into_iter().skip(offset).take(limit).collect();
+
+    // Block numbers are only known once the transactions are mined
+    let resolved = counter_audit::resolve_block_numbers(&entries).await;
+    if !resolved.is_empty() {
+        let mut store = state.counter_store.lock().unwrap();
+        for (tx_hash, block_number) in &resolved {
+            store.set_block_number(&counter_id, tx_hash, *block_number);
+        }
+    }
+    for entry in entries.iter_mut() {
+        if let Some((_, block_number)) = resolved.iter().find(|(tx_hash, _)| entry.tx_hash.as_ref() == Some(tx_hash)) {
+            entry.block_number = Some(*block_number);
+        }
+    }
+
+    Ok(Json(CounterHistoryResponse {
+        counter_id,
+        total,
+        offset,
+        entries,
+    }))
+}
+
+async fn audit_counter(
+    State(state): State<AppState>,
+    Path((account, counter_id)): Path<(String, String)>,
+) -> Result<Json<counter_audit::AuditReport>, (StatusCode, Json<ErrorResponse>)> {
+    let record = find_counter(&state, &account, &counter_id)?;
+    let report = counter_audit::check_counter_history(&record).await;
+    if !report.consistent {
+        println!("Counter {} history is inconsistent:", counter_id);
+        for error in &report.errors {
+            println!("  {}", error);
+        }
+    }
+    Ok(Json(report))
+}
+
+async fn list_apps(State(state): State<AppState>) -> Json<Vec<AppInfo>> {
+    Json(
+        state
+            .registry
+            .list()
+            .into_iter()
+            .map(|app| AppInfo {
+                name: app.name().to_string(),
+                logic_ref: digest_hex(&app.logic_ref()),
+            })
+            .collect(),
+    )
+}
+
+async fn list_app_resources(
+    State(state): State<AppState>,
+    Path((app_name, account)): Path<(String, String)>,
+) -> Result<Json<Vec<AppResourceView>>, (StatusCode, Json<ErrorResponse>)> {
+    let app = state.registry.get(&app_name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Counters are kept in the counter store; its open counters are the account's
+    // spendable counter resources
+    if app_name == counter::COUNTER_APP {
+        let store = state.counter_store.lock().unwrap();
+        let views = store
+            .for_account(&account)
+            .into_iter()
+            .filter(|record| !record.closed)
+            .map(|record| AppResourceView {
+                app: app_name.clone(),
+                commitment: resource_store::commitment_hex(&record.resource),
+                data: app
+                    .decode(&record.resource)
+                    .unwrap_or_else(|e| serde_json::json!({ "error": e })),
+                created_in: record.created_in.clone(),
+                consumed_in: None,
+            })
+            .collect();
+        return Ok(Json(views));
+    }
+
+    let store = state.resources.lock().unwrap();
+    let views = store
+        .for_account(&app_name, &account)
+        .into_iter()
+        .map(|(commitment, stored)| AppResourceView {
+            app: app_name.clone(),
+            commitment: commitment.clone(),
+            data: app
+                .decode(&stored.resource.resource)
+                .unwrap_or_else(|e| serde_json::json!({ "error": e })),
+            created_in: stored.created_in.clone(),
+            consumed_in: stored.consumed_in.clone(),
+        })
+        .collect();
+    Ok(Json(views))
+}
+
+async fn create_app_resource(
+    State(state): State<AppState>,
+    Path(app_name): Path<String>,
+    Json(payload): Json<AppOperationRequest>,
+) -> Result<Json<AppOperationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    run_app_operation(state, &app_name, "create", payload).await
+}
+
+async fn transform_app_resource(
+    State(state): State<AppState>,
+    Path(app_name): Path<String>,
+    Json(payload): Json<AppOperationRequest>,
+) -> Result<Json<AppOperationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    run_app_operation(state, &app_name, "transform", payload).await
+}
+
+async fn consume_app_resource(
+    State(state): State<AppState>,
+    Path(app_name): Path<String>,
+    Json(payload): Json<AppOperationRequest>,
+) -> Result<Json<AppOperationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    run_app_operation(state, &app_name, "consume", payload).await
+}
+
+async fn transfer_app_resource(
+    State(state): State<AppState>,
+    Path(app_name): Path<String>,
+    Json(payload): Json<AppOperationRequest>,
+) -> Result<Json<AppOperationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    run_app_operation(state, &app_name, "transfer", payload).await
+}
+
+// Parses and verifies the recipient's UserKey and checks that the sender signed
+// its nullifier key commitment.
+fn transfer_recipient(payload: &AppOperationRequest) -> Result<user_key::UserKey, String> {
+    let serialized = payload
+        .recipient_user_key
+        .as_deref()
+        .ok_or_else(|| "transfer requires the recipient's UserKey".to_string())?;
+    let recipient = user_key::UserKey::parse_verified(serialized)?;
+    encryption::validate_public_key(&recipient.sepk).map_err(|e| format!("Invalid recipient sepk: {}", e))?;
+    encryption::validate_public_key(&recipient.sdpk).map_err(|e| format!("Invalid recipient sdpk: {}", e))?;
+
+    let recipient_line = format!("Recipient: 0x{}", hex::encode(recipient.cnk));
+    if !payload
+        .signed_message
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case(&recipient_line))
+    {
+        return Err("Message does not contain the expected recipient".to_string());
+    }
+    Ok(recipient)
+}
+
+// The open counter whose current resource is `commitment`, if the signer may run
+// `action` on it, with the counter's lock held. Requests through /apps carry no
+// co-owner approvals, so destructive actions on shared counters go through /execute.
+async fn spendable_counter(
+    state: &AppState,
+    payload: &AppOperationRequest,
+    commitment: &str,
+    action: &str,
+) -> Result<(AppResource, tokio::sync::OwnedMutexGuard<()>), (StatusCode, Json<ErrorResponse>)> {
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error,
+            }),
+        )
+    };
+
+    let counter_action = match action {
+        "transform" => serde_json::from_value::<CounterTransition>(payload.params.clone())
+            .map_err(|e| bad_request(format!("Invalid counter transition: {}", e)))?
+            .action_name(),
+        "consume" => "close",
+        other => other,
+    };
+    let counter_id = state
+        .counter_store
+        .lock()
+        .unwrap()
+        .find_open(commitment)
+        .map(|record| record.counter_id.clone())
+        .ok_or_else(|| bad_request(format!("No open counter with commitment {}", commitment)))?;
+    let selection = CounterSelection {
+        counter_id: Some(counter_id),
+        approvals: vec![],
+    };
+    let (counter_id, resource, nf_key, guard) =
+        select_counter(state, &payload.user_account, &payload.signed_message, &selection, counter_action).await?;
+
+    // Another operation may have advanced the counter while we waited for its lock
+    if !resource_store::commitment_hex(&resource).eq_ignore_ascii_case(commitment) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: format!("Counter {} no longer has commitment {}", counter_id, commitment),
+            }),
+        ));
+    }
+    Ok((AppResource { resource, nf_key }, guard))
+}
+
+// Records a submitted app operation in the store that keeps the app's resources:
+// the counter store for counters, the resource store for other apps.
+fn record_app_update(state: &AppState, update: artifacts::AppUpdate, tx_hash: &str) {
+    if update.app != counter::COUNTER_APP {
+        state.resources.lock().unwrap().apply(update, tx_hash);
+        return;
+    }
+
+    let mut store = state.counter_store.lock().unwrap();
+    let created_in = Some(tx_hash.to_string());
+    let counter_id = update
+        .operation
+        .consumed()
+        .and_then(|consumed| store.find_by_commitment(&consumed.resource))
+        .map(|record| record.counter_id.clone());
+    let result = match (counter_id, &update.operation, update.created) {
+        (None, AppOperation::Create { .. }, Some(created)) => {
+            // Counters created through /apps have a single owner
+            let ownership = Ownership::single(&update.user_account);
+            store.insert_new(&update.user_account, ownership, created.resource, created.nf_key, created_in);
+            Ok(())
+        }
+        (Some(counter_id), AppOperation::Transform { consumed, params, .. }, Some(created)) => {
+            let transition = counter::transition_name(params);
+            store.advance(&counter_id, &consumed.resource, created.resource, transition, &update.user_account, created_in)
+        }
+        (Some(counter_id), AppOperation::Consume { .. }, Some(ephemeral)) => {
+            store.close(&counter_id, ephemeral.resource, &update.user_account, created_in)
+        }
+        (Some(counter_id), AppOperation::Transfer { .. }, None) => match update.sent {
+            Some(sent) => store.transfer(&counter_id, sent, &update.user_account, created_in),
+            None => Err(format!("Transfer of counter {} did not send a resource", counter_id)),
+        },
+        (_, operation, _) => Err(format!("A counter {} is not recorded in the counter store", operation.name())),
+    };
+    match result {
+        Ok(()) => println!("Updated counter state for user: {}", update.user_account),
+        Err(e) => println!("Failed to update counter state: {}", e),
+    }
+}
+
+async fn run_app_operation(
+    state: AppState,
+    app_name: &str,
+    action: &str,
+    payload: AppOperationRequest,
+) -> Result<Json<AppOperationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+
+    println!("Running {} {} for account: {}", app_name, action, user_account);
+
+    let app = state.registry.get(app_name).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content, including the consumed resource
+    let message_check = verify_message_content(&payload.signed_message, action, &user_account, &payload.timestamp)
+        .and_then(|_| match (&payload.commitment, action) {
+            (_, "create") => Ok(()),
+            (Some(commitment), _) => registry::verify_resource_line(&payload.signed_message, commitment),
+            (None, _) => Err(format!("{} requires the commitment of the resource to consume", action)),
+        })
+        .and_then(|_| match action {
+            "transfer" => transfer_recipient(&payload).map(Some),
+            _ => Ok(None),
+        });
+    let recipient = match message_check {
+        Ok(recipient) => recipient,
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Message verification failed: {}", e),
+                }),
+            ));
+        }
+    };
+
+    // App data is only attached to created resources, and must fit its encoding
+    let app_data_check = match action {
+        "create" | "transform" => app_data::encode_all(&payload.app_data).map(|_| ()),
+        _ if payload.app_data.is_empty() => Ok(()),
+        _ => Err(format!("only create and transform attach app data, not {}", action)),
+    };
+    if let Err(e) = app_data_check {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Invalid app data: {}", e),
+            }),
+        ));
+    }
+
+    // A forwarder call is run once before proving, so that a call the Protocol
+    // Adapter would revert on fails here
+    if let Some(call) = &payload.forwarder_call {
+        let forwarder_check = match action {
+            "create" | "transform" => forwarder::dry_run(call).await.map(|_| ()),
+            _ => Err(format!("only create and transform declare forwarder calls, not {}", action)),
+        };
+        if let Err(e) = forwarder_check {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Invalid forwarder call: {}", e),
+                }),
+            ));
+        }
+    }
+
+    // Step 3: Look up the consumed resource and build the operation. Counters are
+    // kept in the counter store, where owners are checked and the counter stays
+    // locked until the store is updated.
+    let (consumed, _counter_guard) = match (action, &payload.commitment) {
+        ("create", _) => (None, None),
+        (_, Some(commitment)) if app_name == counter::COUNTER_APP => {
+            let (consumed, guard) = spendable_counter(&state, &payload, commitment, action).await?;
+            (Some(consumed), Some(guard))
+        }
+        (_, Some(commitment)) => {
+            let store = state.resources.lock().unwrap();
+            let stored = store.spendable(app_name, &user_account, commitment).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ErrorResponse {
+                        error: e,
+                    }),
+                )
+            })?;
+            (Some(stored.resource.clone()), None)
+        }
+        (_, None) => unreachable!("checked with the signed message"),
+    };
+    let params = payload.params.to_string();
+    let operation = match (action, consumed, &recipient) {
+        ("create", _, _) => AppOperation::Create {
+            params,
+            app_data: payload.app_data.clone(),
+            forwarder_call: payload.forwarder_call.clone(),
+        },
+        ("transform", Some(consumed), _) => AppOperation::Transform {
+            consumed,
+            params,
+            app_data: payload.app_data.clone(),
+            forwarder_call: payload.forwarder_call.clone(),
+        },
+        ("transfer", Some(consumed), Some(recipient)) => AppOperation::Transfer {
+            consumed,
+            recipient: recipient.cnk,
+        },
+        (_, Some(consumed), _) => AppOperation::Consume { consumed },
+        (_, None, _) => unreachable!("looked up above"),
+    };
+    let executed = operation.clone();
+
+    // Step 4: Prove, verify, store and submit
+    let receipt = registry::run(app.clone(), state.artifacts.clone(), &user_account, operation)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e,
+                }),
+            )
+        })?;
+    println!("{} {} transaction submitted: {}", app_name, action, receipt.tx_hash);
+
+    // The ephemeral resource balancing a consume is not the caller's to spend
+    let created = receipt
+        .created
+        .as_ref()
+        .filter(|created| !created.resource.is_ephemeral)
+        .map(|created| AppResourceView {
+            app: app_name.to_string(),
+            commitment: resource_store::commitment_hex(&created.resource),
+            data: app
+                .decode(&created.resource)
+                .unwrap_or_else(|e| serde_json::json!({ "error": e })),
+            created_in: Some(receipt.tx_hash.clone()),
+            consumed_in: None,
+        });
+    let sent = match (receipt.sent, &recipient) {
+        (Some(resource), Some(recipient)) => Some(SentResource {
+            recipient_cnk: format!("0x{}", hex::encode(recipient.cnk)),
+            // The keys were checked before proving and the transaction is already
+            // submitted, so a failure here only loses the encrypted copy
+            ciphertext: discovery::DiscoveryPayload::new(&recipient.sdpk, &recipient.sepk, &resource)
+                .and_then(|payload| {
+                    state.payloads.publish(&receipt.tx_hash, payload.clone())?;
+                    Ok(payload.resource_ciphertext)
+                })
+                .unwrap_or_else(|e| {
+                    println!("Failed to publish discovery payload: {}", e);
+                    String::new()
+                }),
+            view: AppResourceView {
+                app: app_name.to_string(),
+                commitment: resource_store::commitment_hex(&resource),
+                data: app
+                    .decode(&resource)
+                    .unwrap_or_else(|e| serde_json::json!({ "error": e })),
+                created_in: Some(receipt.tx_hash.clone()),
+                consumed_in: None,
+            },
+            resource,
+        }),
+        _ => None,
+    };
+    record_app_update(
+        &state,
+        artifacts::AppUpdate {
+            app: app_name.to_string(),
+            user_account: user_account.clone(),
+            operation: executed,
+            created: receipt.created,
+            sent: sent.as_ref().map(|sent| sent.resource.clone()),
+        },
+        &receipt.tx_hash,
+    );
+
+    Ok(Json(AppOperationResponse {
+        transaction_hash: receipt.tx_hash,
+        artifact_hash: receipt.artifact_hash,
+        created,
+        sent,
+    }))
+}
+
+// Secret keys never reach the server: the client trial-decrypts these payloads.
+async fn get_inbox_payloads(
+    State(state): State<AppState>,
+    Query(query): Query<InboxQuery>,
+) -> Result<Json<Vec<discovery::PublishedPayload>>, (StatusCode, Json<ErrorResponse>)> {
+    println!("Listing discovery payloads from block {}", query.from_block);
+    discovery::published(&state.payloads, query.from_block)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e,
+                }),
+            )
+        })
+}
+
+async fn get_transaction_app_data(
+    Path(tx_hash): Path<String>,
+) -> Result<Json<Vec<app_data::DecodedBlob>>, (StatusCode, Json<ErrorResponse>)> {
+    app_data::decode_transaction(&tx_hash).await.map(Json).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })
+}
+
+async fn compose_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<ComposeRequest>,
+) -> Result<Json<ComposeResponse>, (StatusCode, Json<ComposeErrorResponse>)> {
+    let parties: Vec<String> = payload.parts.iter().map(|part| part.user_account.clone()).collect();
+    println!("Composing {} parts from {:?}", payload.parts.len(), parties);
+
+    // Step 1: Every part is signed by its party, over its hash
+    let mut parts = vec![];
+    let mut rejected = vec![];
+    for (i, signed) in payload.parts.into_iter().enumerate() {
+        let part = composition::ComposePart {
+            party: signed.user_account.clone(),
+            transaction: signed.transaction,
+        };
+        let checked = verify_signature(&signed.user_account, &signed.signed_message, &signed.signature)
+            .and_then(|_| {
+                verify_message_content(&signed.signed_message, "compose", &signed.user_account, &signed.timestamp)
+            })
+            .and_then(|_| composition::part_hash(&part))
+            .and_then(|hash| {
+                let line = format!("Transaction: {}", hash);
+                if signed.signed_message.lines().any(|l| l.trim().eq_ignore_ascii_case(&line)) {
+                    Ok(())
+                } else {
+                    Err(format!("Message does not contain \"{}\"", line))
+                }
+            });
+        if let Err(error) = checked {
+            rejected.push(composition::ActionError {
+                part: Some(i),
+                party: Some(signed.user_account),
+                action: None,
+                error,
+            });
+        }
+        parts.push(part);
+    }
+    if !rejected.is_empty() {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ComposeErrorResponse {
+                error: format!("{} parts are not signed by their party", rejected.len()),
+                action_errors: rejected,
+            }),
+        ));
+    }
+
+    // Step 2: Check, compose and submit
+
+    let expected =
+        ExpectedImageIds::for_logic(state.registry.list().iter().map(|app| app.logic_ref()).collect());
+    match composition::compose_and_submit(parts, expected).await {
+        Ok(transaction_hash) => {
+            println!("Composed transaction submitted: {}", transaction_hash);
+            Ok(Json(ComposeResponse {
+                transaction_hash,
+                parties,
             }))
         }
-        Err(e) => {
-            println!("Failed to submit ARM increment transaction: {}", e);
+        Err(composition::ComposeFailure::Rejected(action_errors)) => {
+            for e in &action_errors {
+                println!("  part {:?} action {:?}: {}", e.part, e.action, e.error);
+            }
             Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Failed to submit transaction: {}", e),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ComposeErrorResponse {
+                    error: format!("Composition rejected with {} errors", action_errors.len()),
+                    action_errors,
                 }),
             ))
         }
+        Err(composition::ComposeFailure::Failed(error)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ComposeErrorResponse {
+                error,
+                action_errors: vec![],
+            }),
+        )),
+    }
+}
+
+fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+async fn submit_intent(
+    State(state): State<AppState>,
+    Json(payload): Json<SubmitIntentRequest>,
+) -> Result<Json<SubmitIntentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error,
+            }),
+        )
+    };
+
+    println!("Submitting intent for account: {}", user_account);
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content, including the terms of the intent
+    verify_message_content(&payload.signed_message, "submit_intent", &user_account, &payload.timestamp)
+        .map_err(|e| bad_request(format!("Message verification failed: {}", e)))?;
+    for line in intents::terms_lines(&payload.give, &payload.want, payload.expires_at) {
+        if !payload.signed_message.lines().any(|l| l.trim().eq_ignore_ascii_case(&line)) {
+            return Err(bad_request(format!("Message does not contain \"{}\"", line)));
+        }
+    }
+
+    // Step 3: Decode the partial transaction and check the proofs of its actions. Its
+    // resources may use the logics of the swapped assets or of any registered app.
+    let mut logic: Vec<_> = state.registry.list().iter().map(|app| app.logic_ref()).collect();
+    logic.push(payload.give.logic_digest().map_err(bad_request)?);
+    logic.push(payload.want.logic_digest().map_err(bad_request)?);
+    let expected = ExpectedImageIds::for_logic(logic);
+    let encoded = payload.partial_transaction.clone();
+    let expected_for_partial = expected.clone();
+    let partial = tokio::task::spawn_blocking(move || PartialTransaction::decode(&encoded, &expected_for_partial))
+        .await
+        .map_err(|e| bad_request(format!("Failed to check partial transaction: {}", e)))?
+        .map_err(bad_request)?;
+
+    // Step 4: Add the intent to the pool and look for a match
+    let now = unix_now();
+    let (id, matched) = {
+        let mut pool = state.intents.lock().unwrap();
+        pool.expire(now);
+        let id = pool
+            .submit(&user_account, payload.give, payload.want, payload.expires_at, now, Some(partial))
+            .map_err(bad_request)?;
+        let matched = match pool.find_match(&id) {
+            Some(other) => Some((other.clone(), pool.take_match(&id, &other).map_err(bad_request)?)),
+            None => None,
+        };
+        (id, matched)
+    };
+    println!("Intent {} added to the pool", id);
+
+    // Step 5: Settle the swap with the matching intent
+    let Some((other, (ours, theirs))) = matched else {
+        let intent = state.intents.lock().unwrap().get(&id).cloned();
+        return Ok(Json(SubmitIntentResponse {
+            intent: intent.expect("intent was just added"),
+            matched_with: None,
+            transaction_hash: None,
+        }));
+    };
+    println!("Intent {} matches {}, settling the swap", id, other);
+    let result = intents::settle_match(ours, theirs, expected).await;
+    let mut pool = state.intents.lock().unwrap();
+    pool.settle([&id, &other], &result);
+    let intent = pool.get(&id).cloned().expect("intent was just added");
+    match result {
+        Ok(tx_hash) => {
+            println!("Swap of {} and {} settled in {}", id, other, tx_hash);
+            Ok(Json(SubmitIntentResponse {
+                intent,
+                matched_with: Some(other),
+                transaction_hash: Some(tx_hash),
+            }))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: format!("Failed to settle intent {} with {}: {}", id, other, e.error),
+            }),
+        )),
+    }
+}
+
+async fn list_intents(State(state): State<AppState>) -> Json<Vec<intents::Intent>> {
+    let mut pool = state.intents.lock().unwrap();
+    pool.expire(unix_now());
+    Json(pool.list().to_vec())
+}
+
+async fn cancel_intent(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<CancelIntentRequest>,
+) -> Result<Json<intents::Intent>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error,
+            }),
+        )
+    };
+
+    println!("Cancelling intent {} for account: {}", id, user_account);
+
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+    verify_message_content(&payload.signed_message, "cancel_intent", &user_account, &payload.timestamp)
+        .map_err(|e| bad_request(format!("Message verification failed: {}", e)))?;
+    let intent_line = format!("Intent: {}", id);
+    if !payload.signed_message.lines().any(|l| l.trim().eq_ignore_ascii_case(&intent_line)) {
+        return Err(bad_request("Message does not contain the intent to cancel".to_string()));
+    }
+
+    let mut pool = state.intents.lock().unwrap();
+    pool.expire(unix_now());
+    pool.cancel(&id, &user_account).map_err(bad_request)?;
+    Ok(Json(pool.get(&id).cloned().expect("cancelled intent exists")))
+}
+
+async fn get_transaction_forwarder_calls(
+    Path(tx_hash): Path<String>,
+) -> Result<Json<Vec<forwarder::ExecutedForwarderCall>>, (StatusCode, Json<ErrorResponse>)> {
+    forwarder::executed_calls(&tx_hash).await.map(Json).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })
+}
+
+async fn list_artifacts(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<artifacts::ArtifactMetadata>>, (StatusCode, Json<ErrorResponse>)> {
+    state.artifacts.list().map(Json).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })
+}
+
+async fn export_artifact(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+    Query(query): Query<ArtifactExportQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let not_found = |e: String| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    };
+
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => {
+            let record = state.artifacts.load_record(&hash).map_err(not_found)?;
+            Ok(Json(record).into_response())
+        }
+        "bincode" => {
+            let bytes = state.artifacts.load_bincode(&hash).map_err(not_found)?;
+            Ok((
+                [
+                    (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+                    (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.bin\"", hash)),
+                ],
+                bytes,
+            ).into_response())
+        }
+        other => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Unknown export format: {}. Use json or bincode", other),
+            }),
+        )),
+    }
+}
+
+async fn resubmit_artifact(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+    Json(payload): Json<ResubmitArtifactRequest>,
+) -> Result<Json<ResubmitArtifactResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let user_account = payload.user_account.clone();
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error,
+            }),
+        )
+    };
+
+    println!("Resubmitting artifact {} for account: {}", hash, user_account);
+
+    // Step 1: Verify the signature
+    if let Err(e) = verify_signature(&user_account, &payload.signed_message, &payload.signature) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: format!("Signature verification failed: {}", e),
+            }),
+        ));
+    }
+
+    // Step 2: Verify the message content, which names the artifact so a signed
+    // request cannot be replayed against another one
+    verify_message_content(&payload.signed_message, "resubmit", &user_account, &payload.timestamp)
+        .map_err(|e| bad_request(format!("Message verification failed: {}", e)))?;
+    let artifact_line = format!("Artifact: {}", hash);
+    if !payload.signed_message.lines().any(|l| l.trim().eq_ignore_ascii_case(&artifact_line)) {
+        return Err(bad_request("Message does not contain the artifact to resubmit".to_string()));
+    }
+
+    // Step 3: Only the account the artifact was proved for may resubmit it.
+    // Artifacts without an account can only be resubmitted from the CLI.
+    let record = state.artifacts.load_record(&hash).map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+    match record.metadata.user_account.as_deref() {
+        Some(account) if account.eq_ignore_ascii_case(&user_account) => {}
+        _ => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse {
+                    error: format!("Artifact {} was not proved for account {}", hash, user_account),
+                }),
+            ));
+        }
+    }
+    artifacts::ensure_resubmittable(&record.metadata).map_err(|e| {
+        (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // Step 4: Lock the counters the artifact consumes until the store records the
+    // outcome, so no other operation consumes the same resources meanwhile. Locks are
+    // taken in counter id order.
+    let counter_ids: std::collections::BTreeSet<String> = match state.artifacts.load_inputs(&hash) {
+        Ok(inputs) => {
+            let store = state.counter_store.lock().unwrap();
+            inputs
+                .counter_updates(Some(&user_account))
+                .iter()
+                .filter_map(|update| update.consumed.as_ref())
+                .filter_map(|consumed| store.find_by_commitment(consumed))
+                .map(|record| record.counter_id.clone())
+                .collect()
+        }
+        Err(_) => Default::default(),
+    };
+    let mut _counter_guards = vec![];
+    for counter_id in &counter_ids {
+        _counter_guards.push(state.counter_locks.lock(counter_id).await);
+    }
+
+    let outcome = artifacts::resubmit(&state.artifacts, &hash).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e,
+            }),
+        )
+    })?;
+
+    // A resubmitted counter transaction moves the counter forward just like the
+    // original endpoint would have
+    {
+        let mut store = state.counter_store.lock().unwrap();
+        for update in outcome.counter_updates {
+            println!("Updated counter state for user: {}", update.user_account);
+            let existing = update
+                .consumed
+                .as_ref()
+                .and_then(|consumed| Some((store.find_by_commitment(consumed)?.counter_id.clone(), consumed.clone())));
+            match existing {
+                Some((counter_id, _)) if update.transition == "close" => {
+                    if let Err(e) = store.close(&counter_id, update.created, &update.user_account, Some(outcome.tx_hash.clone())) {
+                        println!("Failed to close counter state: {}", e);
+                    }
+                }
+                Some((counter_id, consumed)) => {
+                    if let Err(e) = store.advance(&counter_id, &consumed, update.created, &update.transition, &update.user_account, Some(outcome.tx_hash.clone())) {
+                        println!("Failed to update counter state: {}", e);
+                    }
+                }
+                None if update.transition == "initialize" => {
+                    // Co-owners are not part of the proving inputs, so a resubmitted
+                    // initialization records a single-owner counter
+                    let ownership = Ownership::single(&update.user_account);
+                    store.insert_new(&update.user_account, ownership, update.created, update.nf_key, Some(outcome.tx_hash.clone()));
+                }
+                None => {
+                    println!(
+                        "Counter consumed by the {} in {} is not in the store; its state is not recorded",
+                        update.transition, outcome.tx_hash
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(update) = outcome.app_update {
+        record_app_update(&state, update, &outcome.tx_hash);
+    }
+
+    Ok(Json(ResubmitArtifactResponse {
+        transaction_hash: outcome.tx_hash,
+        path: outcome.path,
+        artifact_hash: outcome.artifact_hash,
+        unknown_roots: outcome.unknown_roots,
+    }))
+}
+
+fn record_submission(artifacts: &ArtifactStore, hash: &str, status: ArtifactStatus) {
+    if let Err(e) = artifacts.set_status(hash, status) {
+        println!("Failed to update artifact {}: {}", hash, e);
     }
 }
 
@@ -557,6 +2378,17 @@ async fn emit_increment_transaction(
 async fn main() {
     // Load environment variables from .env file
     dotenv::dotenv().ok();
+
+    if let Err(e) = verification::load_expected_compliance_id() {
+        println!("{}", e);
+        std::process::exit(1);
+    }
+
+    // Run a CLI subcommand instead of the server if one was given
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cli::run(&args).await {
+        std::process::exit(code);
+    }
     
     // Environment configuration check
     println!("Environment Configuration:");
@@ -567,8 +2399,41 @@ async fn main() {
     println!();
 
     // Create the application state
+    let artifacts = match ArtifactStore::from_env() {
+        Ok(store) => store,
+        Err(e) => {
+            println!("Failed to open artifact store: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let payloads = match PayloadStore::from_env() {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            println!("Failed to open payload store: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let counter_store = match CounterStore::from_env() {
+        Ok(store) => store,
+        Err(e) => {
+            println!("Failed to open counter store: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let artifacts = Arc::new(artifacts);
+    let batch_config = BatchConfig::from_env();
     let app_state = AppState {
-        counter_store: Arc::new(Mutex::new(HashMap::new())),
+        counter_store: Arc::new(Mutex::new(counter_store)),
+        counter_locks: CounterLocks::default(),
+        batcher: Batcher::spawn(batch_config, artifacts.clone()),
+        artifacts,
+        registry: Arc::new(Registry::with_defaults()),
+        resources: Arc::new(Mutex::new(ResourceStore::default())),
+        payloads,
+        intents: Arc::new(Mutex::new(IntentPool::default())),
     };
     
     let app = Router::new()
@@ -578,7 +2443,31 @@ async fn main() {
         .route("/emit-real-transaction", post(emit_real_transaction))
         .route("/emit-counter-transaction", post(emit_counter_transaction))
         .route("/emit-increment-transaction", post(emit_increment_transaction))
-        // Future: .route("/execute", post(execute_function)) for ARM counter operations
+        .route("/emit-batched-increment-transaction", post(emit_batched_increment_transaction))
+        .route("/emit-decrement-transaction", post(emit_decrement_transaction))
+        .route("/emit-add-transaction", post(emit_add_transaction))
+        .route("/emit-reset-transaction", post(emit_reset_transaction))
+        .route("/counters/:account", get(get_account_counters))
+        .route("/counters/:account/:counter_id", get(get_counter))
+        .route("/counters/:account/:counter_id/history", get(get_counter_history))
+        .route("/counters/:account/:counter_id/audit", get(audit_counter))
+        .route("/apps", get(list_apps))
+        .route("/apps/:app/create", post(create_app_resource))
+        .route("/apps/:app/transform", post(transform_app_resource))
+        .route("/apps/:app/consume", post(consume_app_resource))
+        .route("/apps/:app/transfer", post(transfer_app_resource))
+        .route("/inbox", get(get_inbox_payloads))
+        .route("/transactions/:tx_hash/app-data", get(get_transaction_app_data))
+        .route("/transactions/:tx_hash/forwarder-calls", get(get_transaction_forwarder_calls))
+        .route("/transactions/compose", post(compose_transaction))
+        .route("/apps/:app/resources/:account", get(list_app_resources))
+        .route("/intents", post(submit_intent).get(list_intents))
+        .route("/intents/:id/cancel", post(cancel_intent))
+        .route("/artifacts", get(list_artifacts))
+        .route("/artifacts/:hash", get(export_artifact))
+        .route("/artifacts/:hash/resubmit", post(resubmit_artifact))
+        .route("/emit-close-transaction", post(emit_close_transaction))
+        .route("/execute", post(execute_counter_action))
         .with_state(app_state)
         .layer(CorsLayer::permissive());
 
@@ -592,6 +2481,32 @@ async fn main() {
     println!("  POST /emit-real-transaction - Real ARM transaction with ZK proofs");
     println!("  POST /emit-counter-transaction - ARM counter initialization");
     println!("  POST /emit-increment-transaction - ARM counter increment");
+    println!("  POST /emit-batched-increment-transaction - ARM counter increment, batched ({} actions / {}ms)", batch_config.max_size, batch_config.window.as_millis());
+    println!("  POST /emit-decrement-transaction - ARM counter decrement (rejects underflow)");
+    println!("  POST /emit-add-transaction - ARM counter add-by-N");
+    println!("  POST /emit-reset-transaction - ARM counter reset to zero");
+    println!("  POST /emit-close-transaction - Close the counter and delete its nullifier key");
+    println!("  POST /execute - Unified counter endpoint (initialize, increment, decrement, add, reset, close)");
+    println!("  GET  /counters/:account - Counters of an account with value and on-chain status");
+    println!("  GET  /counters/:account/:counter_id - A single counter");
+    println!("  GET  /counters/:account/:counter_id/history - Paginated history of a counter");
+    println!("  GET  /counters/:account/:counter_id/audit - On-chain consistency check of a counter's history");
+    println!("  GET  /apps - Registered ARM applications");
+    println!("  POST /apps/:app/create - Create a resource of a registered app");
+    println!("  POST /apps/:app/transform - Consume a resource and create its successor");
+    println!("  POST /apps/:app/consume - Consume a resource without a successor");
+    println!("  POST /apps/:app/transfer - Send a resource to another user's UserKey");
+    println!("  GET  /inbox - List discovery payloads of executed transactions");
+    println!("  GET  /transactions/:tx_hash/app-data - Decode the app data blobs of an executed transaction");
+    println!("  GET  /transactions/:tx_hash/forwarder-calls - Forwarder calls executed by a transaction");
+    println!("  POST /transactions/compose - Compose and submit client-proved actions of several parties");
+    println!("  GET  /apps/:app/resources/:account - Resources of an app created for an account");
+    println!("  POST /intents - Submit a partial transaction as a swap intent; matches are settled at once");
+    println!("  GET  /intents - Intents in the pool with their status");
+    println!("  POST /intents/:id/cancel - Cancel an open intent");
+    println!("  GET  /artifacts - Stored proved transactions");
+    println!("  GET  /artifacts/:hash?format=json|bincode - Export a proved transaction");
+    println!("  POST /artifacts/:hash/resubmit - Resubmit, proving again only if its root is unknown");
     println!("Frontend: http://localhost:5173 (run separately)");
     println!("EVM Protocol Adapter integration: Enabled");
     println!("ARM counter operations: Enabled with RISC0 proving");
@@ -645,42 +2560,45 @@ fn create_increment_tx_with_merkle_path(
     counter_nf_key: NullifierKey,
     merkle_path: MerklePath<32>,
 ) -> (arm_risc0::transaction::Transaction, Resource) {
+    use arm_risc0::transaction::{Delta, Transaction};
+
+    let (action, delta_witness, new_counter) =
+        create_increment_action_with_merkle_path(counter_resource, counter_nf_key, merkle_path);
+    let mut tx = Transaction::create(vec![action], Delta::Witness(delta_witness));
+    tx.generate_delta_proof();
+    (tx, new_counter)
+}
+
+// Builds the proved increment action without wrapping it in a transaction, so that
+// several actions can share one transaction and one delta proof (see batch.rs).
+fn create_increment_action_with_merkle_path(
+    counter_resource: Resource,
+    counter_nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+) -> (arm_risc0::action::Action, arm_risc0::delta_proof::DeltaWitness, Resource) {
     use arm_risc0::{
         action::Action,
         delta_proof::DeltaWitness,
-        transaction::{Delta, Transaction},
     };
 
     println!("Creating increment with merkle path from Protocol Adapter");
-    
-    // Create a default merkle path for now - this is still not ideal but should be better than completely default
-    // In a full implementation, we'd need to construct the actual merkle path for the commitment
-    // let merkle_path = MerklePath::<32>::default(); 
-    
-            // FIRST: Let's test your hypothesis by creating two increment resources and comparing commitments
-        println!("Testing commitment determinism...");
-        let test_counter_1 = app::increment::increment_counter(&counter_resource, &counter_nf_key);
-        let test_counter_2 = app::increment::increment_counter(&counter_resource, &counter_nf_key);
-        
-        println!("Test counter 1 commitment: 0x{}", hex::encode(test_counter_1.commitment().as_bytes()));
-        println!("Test counter 2 commitment: 0x{}", hex::encode(test_counter_2.commitment().as_bytes()));
-        println!("Commitments are identical: {}", test_counter_1.commitment().as_bytes() == test_counter_2.commitment().as_bytes());
-        
-        // Use the ARM increment logic but with awareness of the latest root
-        let new_counter = test_counter_1; // Use the first test counter
-        
-        println!("Original counter commitment: 0x{}", hex::encode(counter_resource.commitment().as_bytes()));
-        println!("New counter commitment: 0x{}", hex::encode(new_counter.commitment().as_bytes()));
-        println!("Original counter value: {}", u128::from_le_bytes(counter_resource.value_ref[0..16].try_into().unwrap_or([0; 16])));
-        println!("New counter value: {}", u128::from_le_bytes(new_counter.value_ref[0..16].try_into().unwrap_or([0; 16])));
-        
-        let (compliance_unit, rcv) = app::generate_compliance_proof(
-            counter_resource.clone(),
-            counter_nf_key.clone(),
-            merkle_path, // Using real merkle path from Protocol Adapter
-            new_counter.clone(),
-        );
-        
+
+    // Resources draw fresh randomness, so commitments differ between runs; see
+    // seeded.rs for reproducible transactions
+    let new_counter = app::increment::increment_counter(&counter_resource, &counter_nf_key);
+
+    println!("Original counter commitment: 0x{}", hex::encode(counter_resource.commitment().as_bytes()));
+    println!("New counter commitment: 0x{}", hex::encode(new_counter.commitment().as_bytes()));
+    println!("Original counter value: {}", counter::counter_value(&counter_resource));
+    println!("New counter value: {}", counter::counter_value(&new_counter));
+
+    let (compliance_unit, rcv) = app::generate_compliance_proof(
+        counter_resource.clone(),
+        counter_nf_key.clone(),
+        merkle_path, // Using real merkle path from Protocol Adapter
+        new_counter.clone(),
+    );
+
     let logic_verifier_inputs = app::generate_logic_proofs(
         counter_resource,
         counter_nf_key,
@@ -689,65 +2607,173 @@ fn create_increment_tx_with_merkle_path(
 
     let action = Action::new(vec![compliance_unit], logic_verifier_inputs);
     let delta_witness = DeltaWitness::from_bytes(&rcv);
-    let mut tx = Transaction::create(vec![action], Delta::Witness(delta_witness));
-    tx.generate_delta_proof();
-    (tx, new_counter)
+    (action, delta_witness, new_counter)
 }
 
 // Signature verification functions
+
+// Checks that `signature` is the account's EIP-191 (`personal_sign`) signature of
+// `message`, by recovering the signer's address. Each signed message is accepted
+// once; a second request carrying the same message is rejected as a replay.
 fn verify_signature(user_account: &str, message: &str, signature: &str) -> Result<(), String> {
-    // TODO: Implement proper ECDSA signature verification
-    // For now, just basic validation
-    
-    if signature.len() < 10 {
-        return Err("Signature too short".to_string());
+    let expected: alloy::primitives::Address = user_account
+        .parse()
+        .map_err(|e| format!("Invalid account address {}: {}", user_account, e))?;
+    let signature: alloy::primitives::Signature = signature
+        .parse()
+        .map_err(|e| format!("Invalid signature format: {}", e))?;
+    let signer = signature
+        .recover_address_from_msg(message.as_bytes())
+        .map_err(|e| format!("Failed to recover signer: {}", e))?;
+
+    if signer != expected {
+        return Err(format!("Message was signed by {}, not {}", signer, expected));
     }
-    
-    if !signature.starts_with("0x") {
-        return Err("Invalid signature format".to_string());
+    claim_signed_message(&signer, message)?;
+    println!("Signature verified for account: {}", user_account);
+    Ok(())
+}
+
+// Signed messages already accepted, keyed by signer and message hash. Entries are
+// kept for twice the freshness window; older messages fail the timestamp check anyway.
+static USED_MESSAGES: std::sync::OnceLock<Mutex<std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>>> =
+    std::sync::OnceLock::new();
+
+const MESSAGE_MAX_AGE_MINUTES: i64 = 5;
+const MESSAGE_MAX_SKEW_MINUTES: i64 = 1;
+
+// Keyed by the message rather than the signature bytes, so a re-encoded
+// (malleated) signature of the same message is still caught.
+fn claim_signed_message(signer: &alloy::primitives::Address, message: &str) -> Result<(), String> {
+    let key = format!("{}:{}", signer, alloy::primitives::keccak256(message.as_bytes()));
+    let now = chrono::Utc::now();
+    let mut used = USED_MESSAGES
+        .get_or_init(|| Mutex::new(std::collections::HashMap::new()))
+        .lock()
+        .map_err(|_| "Signature registry lock poisoned".to_string())?;
+
+    used.retain(|_, seen| now.signed_duration_since(*seen).num_minutes() <= 2 * MESSAGE_MAX_AGE_MINUTES);
+    if used.contains_key(&key) {
+        return Err("Signature was already used".to_string());
     }
-    
-    println!("[MOCK] Signature verification for account: {}", user_account);
-    println!("[MOCK] Message length: {} chars", message.len());
-    println!("[MOCK] Signature: {}...", &signature[0..20]);
-    
-    // TODO: Replace with real signature verification:
-    // 1. Recover public key from signature + message
-    // 2. Derive address from public key  
-    // 3. Compare with user_account
-    
+    used.insert(key, now);
     Ok(())
 }
 
+// Returns the value of the single `Name: value` line in `message`.
+fn message_field<'a>(message: &'a str, name: &str) -> Result<&'a str, String> {
+    let prefix = format!("{}: ", name);
+    let mut values = message.lines().filter_map(|line| line.strip_prefix(prefix.as_str()));
+    match (values.next(), values.next()) {
+        (Some(value), None) => Ok(value),
+        (None, _) => Err(format!("Message does not contain a {} line", name)),
+        (Some(_), Some(_)) => Err(format!("Message contains more than one {} line", name)),
+    }
+}
+
 fn verify_message_content(message: &str, expected_action: &str, expected_account: &str, timestamp: &str) -> Result<(), String> {
-    // Verify the message contains the expected action
-    let action_line = format!("Action: {}", expected_action.to_uppercase());
-    if !message.contains(&action_line) {
+    // Verify the signed action is exactly the expected one
+    if message_field(message, "Action")? != expected_action.to_uppercase() {
         return Err("Message does not contain expected action".to_string());
     }
-    
-    // Verify the message contains the expected account
-    let account_line = format!("Account: {}", expected_account);
-    if !message.contains(&account_line) {
+
+    // Verify the signed account is the requesting account
+    if !message_field(message, "Account")?.eq_ignore_ascii_case(expected_account) {
         return Err("Message does not contain expected account".to_string());
     }
-    
+
+    // Verify the signed timestamp is the request timestamp
+    if message_field(message, "Timestamp")? != timestamp {
+        return Err("Message timestamp does not match the request timestamp".to_string());
+    }
+
     // Verify timestamp is recent (within 5 minutes)
     if let Ok(msg_time) = chrono::DateTime::parse_from_rfc3339(timestamp) {
         let now = chrono::Utc::now();
         let diff = now.signed_duration_since(msg_time.with_timezone(&chrono::Utc));
-        
-        if diff.num_minutes() > 5 {
+
+        if diff.num_minutes() > MESSAGE_MAX_AGE_MINUTES {
             return Err("Message timestamp is too old".to_string());
         }
-        
-        if diff.num_minutes() < -1 {
+
+        if diff.num_minutes() < -MESSAGE_MAX_SKEW_MINUTES {
             return Err("Message timestamp is in the future".to_string());
         }
     } else {
         return Err("Invalid timestamp format".to_string());
     }
-    
+
     println!("Message content verification passed");
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::SignerSync;
+
+    fn sign(signer: &PrivateKeySigner, message: &str) -> String {
+        let signature = signer.sign_message_sync(message.as_bytes()).unwrap();
+        format!("0x{}", hex::encode(signature.as_bytes()))
+    }
+
+    #[test]
+    fn message_lines_must_match_exactly() {
+        let account = PrivateKeySigner::random().address().to_string();
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let message = counter::signing_message("increment", &account, &timestamp);
+        assert!(verify_message_content(&message, "increment", &account, &timestamp).is_ok());
+        assert!(verify_message_content(&message, "increment", &account.to_lowercase(), &timestamp).is_ok());
+
+        // A prefix of the signed action or an action smuggled into another line does not count
+        let longer = message.replace("Action: INCREMENT", "Action: INCREMENT_ALL");
+        assert!(verify_message_content(&longer, "increment", &account, &timestamp).is_err());
+        let smuggled = message.replace("Action: INCREMENT", "Action: DECREMENT\nNote: Action: INCREMENT");
+        assert!(verify_message_content(&smuggled, "increment", &account, &timestamp).is_err());
+        let duplicated = message.replace("Action: INCREMENT", "Action: INCREMENT\nAction: RESET");
+        assert!(verify_message_content(&duplicated, "increment", &account, &timestamp)
+            .unwrap_err()
+            .contains("more than one Action line"));
+    }
+
+    #[test]
+    fn signed_timestamp_must_be_the_request_timestamp() {
+        let account = PrivateKeySigner::random().address().to_string();
+        let signed_at = chrono::Utc::now().to_rfc3339();
+        let message = counter::signing_message("increment", &account, &signed_at);
+        let requested_at = (chrono::Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+        assert!(verify_message_content(&message, "increment", &account, &requested_at)
+            .unwrap_err()
+            .contains("does not match"));
+    }
+
+    #[test]
+    fn stale_and_future_timestamps_are_rejected() {
+        let account = PrivateKeySigner::random().address().to_string();
+        for (offset, error) in [(-10, "too old"), (10, "in the future")] {
+            let timestamp = (chrono::Utc::now() + chrono::Duration::minutes(offset)).to_rfc3339();
+            let message = counter::signing_message("increment", &account, &timestamp);
+            assert!(verify_message_content(&message, "increment", &account, &timestamp).unwrap_err().contains(error));
+        }
+    }
+
+    #[test]
+    fn signature_is_accepted_once() {
+        let signer = PrivateKeySigner::random();
+        let account = signer.address().to_string();
+        let message = counter::signing_message("increment", &account, &chrono::Utc::now().to_rfc3339());
+        let signature = sign(&signer, &message);
+
+        assert!(verify_signature(&account, &message, &signature).is_ok());
+        assert!(verify_signature(&account, &message, &signature).unwrap_err().contains("already used"));
+    }
+
+    #[test]
+    fn signature_from_another_key_is_rejected() {
+        let account = PrivateKeySigner::random().address().to_string();
+        let message = counter::signing_message("increment", &account, &chrono::Utc::now().to_rfc3339());
+        let signature = sign(&PrivateKeySigner::random(), &message);
+        assert!(verify_signature(&account, &message, &signature).is_err());
+    }
+}
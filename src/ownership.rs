@@ -0,0 +1,239 @@
+// Owners of a counter and the approvals needed to act on it.
+//
+// Any owner may increment, decrement or add to a counter. Destructive operations
+// (reset, close, transfer) on a counter with a threshold of M need signed approvals from M
+// distinct owners, counting the requester. On shared counters every signed message
+// carries an extra `Counter: <id>` line, so a signature for one counter cannot be
+// replayed against another.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ownership {
+    pub owners: Vec<String>,
+    // Number of owners that must sign a destructive operation
+    pub threshold: usize,
+}
+
+impl Ownership {
+    pub fn single(owner: &str) -> Self {
+        Ownership {
+            owners: vec![owner.to_string()],
+            threshold: 1,
+        }
+    }
+
+    // The creator is always an owner; duplicate accounts are dropped. Co-owners must
+    // be Ethereum addresses, since they later sign approvals with those accounts.
+    pub fn new(creator: &str, co_owners: Vec<String>, threshold: Option<usize>) -> Result<Self, String> {
+        let mut owners = vec![creator.to_string()];
+        for owner in co_owners {
+            Address::from_str(&owner).map_err(|e| format!("Invalid co-owner address {}: {}", owner, e))?;
+            if !owners.iter().any(|o| o.eq_ignore_ascii_case(&owner)) {
+                owners.push(owner);
+            }
+        }
+
+        let threshold = threshold.unwrap_or(1);
+        if threshold == 0 || threshold > owners.len() {
+            return Err(format!(
+                "Threshold must be between 1 and the number of owners ({}), got {}",
+                owners.len(),
+                threshold
+            ));
+        }
+        Ok(Ownership { owners, threshold })
+    }
+
+    pub fn is_owner(&self, account: &str) -> bool {
+        self.owners.iter().any(|owner| owner.eq_ignore_ascii_case(account))
+    }
+
+    pub fn is_shared(&self) -> bool {
+        self.owners.len() > 1
+    }
+}
+
+pub fn is_destructive(action: &str) -> bool {
+    matches!(action, "reset" | "close" | "transfer")
+}
+
+// A co-owner's signed approval of a counter action.
+#[derive(Deserialize, Clone)]
+pub struct Approval {
+    pub user_account: String,
+    pub signature: String,
+    pub signed_message: String,
+    pub timestamp: String,
+}
+
+// Which counter a signed request acts on (the caller's active counter if omitted)
+// and the co-owner approvals it carries.
+#[derive(Deserialize, Default, Clone)]
+pub struct CounterSelection {
+    pub counter_id: Option<String>,
+    #[serde(default)]
+    pub approvals: Vec<Approval>,
+}
+
+fn verify_counter_line(message: &str, counter_id: &str) -> Result<(), String> {
+    let counter_line = format!("Counter: {}", counter_id);
+    if !message.lines().any(|line| line.trim().eq_ignore_ascii_case(&counter_line)) {
+        return Err("Message does not contain the expected counter".to_string());
+    }
+    Ok(())
+}
+
+// Checks that `requester` may run `action` on the counter and, for destructive
+// actions, that enough owners approved it. The requester's own signature has
+// already been checked by the caller.
+pub fn authorize(
+    ownership: &Ownership,
+    counter_id: &str,
+    action: &str,
+    requester: &str,
+    requester_message: &str,
+    approvals: &[Approval],
+) -> Result<(), String> {
+    if !ownership.is_owner(requester) {
+        return Err(format!("{} is not an owner of counter {}", requester, counter_id));
+    }
+    if ownership.is_shared() {
+        verify_counter_line(requester_message, counter_id)?;
+    }
+    if !is_destructive(action) || ownership.threshold <= 1 {
+        return Ok(());
+    }
+
+    let mut approvers = HashSet::from([requester.to_lowercase()]);
+    for approval in approvals {
+        if !ownership.is_owner(&approval.user_account) {
+            return Err(format!("Approval from {}, who is not an owner", approval.user_account));
+        }
+        crate::verify_signature(&approval.user_account, &approval.signed_message, &approval.signature)
+            .and_then(|_| {
+                crate::verify_message_content(
+                    &approval.signed_message,
+                    action,
+                    &approval.user_account,
+                    &approval.timestamp,
+                )
+            })
+            .and_then(|_| verify_counter_line(&approval.signed_message, counter_id))
+            .map_err(|e| format!("Invalid approval from {}: {}", approval.user_account, e))?;
+        approvers.insert(approval.user_account.to_lowercase());
+    }
+
+    if approvers.len() < ownership.threshold {
+        return Err(format!(
+            "{} requires approval from {} of {} owners, got {}",
+            action,
+            ownership.threshold,
+            ownership.owners.len(),
+            approvers.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::signers::SignerSync;
+
+    const COUNTER_ID: &str = "0xc0ffee";
+
+    fn account(signer: &PrivateKeySigner) -> String {
+        signer.address().to_string()
+    }
+
+    fn signed(signer: &PrivateKeySigner, action: &str, counter_id: &str) -> Approval {
+        let user_account = account(signer);
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let signed_message = format!(
+            "{}\nCounter: {}",
+            crate::counter::signing_message(action, &user_account, &timestamp),
+            counter_id
+        );
+        let signature = signer.sign_message_sync(signed_message.as_bytes()).unwrap();
+        Approval {
+            user_account,
+            signature: format!("0x{}", alloy::primitives::hex::encode(signature.as_bytes())),
+            signed_message,
+            timestamp,
+        }
+    }
+
+    fn shared_counter(owners: &[PrivateKeySigner], threshold: usize) -> Ownership {
+        let co_owners = owners[1..].iter().map(account).collect();
+        Ownership::new(&account(&owners[0]), co_owners, Some(threshold)).unwrap()
+    }
+
+    #[test]
+    fn destructive_action_needs_threshold_of_signed_approvals() {
+        let owners: Vec<PrivateKeySigner> = (0..3).map(|_| PrivateKeySigner::random()).collect();
+        let ownership = shared_counter(&owners, 2);
+        let request = signed(&owners[0], "reset", COUNTER_ID);
+        let approve = |approvals: &[Approval]| {
+            authorize(&ownership, COUNTER_ID, "reset", &request.user_account, &request.signed_message, approvals)
+        };
+
+        assert!(approve(&[]).is_err());
+        assert!(approve(&[signed(&owners[1], "reset", COUNTER_ID)]).is_ok());
+        // An approval for another counter or another action does not count
+        assert!(approve(&[signed(&owners[1], "reset", "0xbeef")]).is_err());
+        assert!(approve(&[signed(&owners[1], "close", COUNTER_ID)]).is_err());
+    }
+
+    #[test]
+    fn approval_signed_by_another_key_is_rejected() {
+        let owners: Vec<PrivateKeySigner> = (0..2).map(|_| PrivateKeySigner::random()).collect();
+        let ownership = shared_counter(&owners, 2);
+        let request = signed(&owners[0], "close", COUNTER_ID);
+
+        // Signed by an outsider but claiming to come from the second owner
+        let mut forged = signed(&PrivateKeySigner::random(), "close", COUNTER_ID);
+        forged.signed_message = forged.signed_message.replace(&forged.user_account, &account(&owners[1]));
+        forged.user_account = account(&owners[1]);
+
+        let result =
+            authorize(&ownership, COUNTER_ID, "close", &request.user_account, &request.signed_message, &[forged]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn non_owner_cannot_act() {
+        let owners: Vec<PrivateKeySigner> = (0..2).map(|_| PrivateKeySigner::random()).collect();
+        let ownership = shared_counter(&owners, 1);
+        let outsider = signed(&PrivateKeySigner::random(), "increment", COUNTER_ID);
+        let result =
+            authorize(&ownership, COUNTER_ID, "increment", &outsider.user_account, &outsider.signed_message, &[]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn co_owners_must_be_addresses() {
+        let creator = account(&PrivateKeySigner::random());
+        let result = Ownership::new(&creator, vec!["alice".to_string()], Some(1));
+        assert!(result.unwrap_err().contains("Invalid co-owner address alice"));
+    }
+
+    #[test]
+    fn approval_cannot_be_replayed() {
+        let owners: Vec<PrivateKeySigner> = (0..2).map(|_| PrivateKeySigner::random()).collect();
+        let ownership = shared_counter(&owners, 2);
+        let request = signed(&owners[0], "reset", COUNTER_ID);
+        let approval = signed(&owners[1], "reset", COUNTER_ID);
+        let approve = || {
+            authorize(&ownership, COUNTER_ID, "reset", &request.user_account, &request.signed_message, &[approval.clone()])
+        };
+
+        assert!(approve().is_ok());
+        assert!(approve().unwrap_err().contains("already used"));
+    }
+}
@@ -0,0 +1,314 @@
+// Registry of ARM applications served by the generic /apps endpoints.
+//
+// Each application describes its logic (the verifying key of its resource logic),
+// how its resource data is encoded in `value_ref`, and how to build the witnesses
+// for the things the backend does with resources:
+//
+//   create     prove a fresh resource of the app
+//   transform  consume a resource and create its successor
+//   consume    consume a resource without a successor; an ephemeral resource
+//              balances the action and is returned as the created resource
+//   transfer   consume a resource and create a copy owned by someone else, bound
+//              to their nullifier key commitment
+//
+// Create and transform may attach typed app data (see `app_data`) and a forwarder
+// call (see `forwarder`) to the created resource if the app's logic commits app
+// data to the logic instance.
+//
+// Successors are created with the nullifier key of the consumed resource. The
+// counter is registered as one implementation (`counter::CounterApp`). Every counter
+// endpoint proves and submits through `run`; counter resources are kept in the
+// counter store, the resources of other apps in the resource store.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use alloy::primitives::B256;
+use arm_risc0::merkle_path::MerklePath;
+use arm_risc0::nullifier_key::{NullifierKey, NullifierKeyCommitment};
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::Transaction as ArmTransaction;
+use evm_protocol_adapter_bindings::call::protocol_adapter;
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use risc0_zkvm::sha::Digest;
+use serde::{Deserialize, Serialize};
+
+use crate::app_data::{self, AppDataBlob, AppDataItem};
+use crate::artifacts::{execute_confirmed, ArtifactStatus, ArtifactStore, ProvingInputs};
+use crate::counter::CounterApp;
+use crate::forwarder::ForwarderCall;
+use crate::verification::{self, ExpectedImageIds};
+
+// A resource of an app together with the key needed to consume it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AppResource {
+    pub resource: Resource,
+    pub nf_key: NullifierKey,
+}
+
+pub trait Application: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Verifying key of the app's resource logic
+    fn logic_ref(&self) -> Digest;
+
+    // Human-readable view of a resource's data, decoded from `value_ref`.
+    fn decode(&self, resource: &Resource) -> Result<serde_json::Value, String>;
+
+    // Whether the app's logic witness takes app data for the created resource.
+    // Apps that do not are never passed any.
+    fn commits_app_data(&self) -> bool {
+        false
+    }
+
+    fn create(
+        &self,
+        params: &serde_json::Value,
+        app_data: &[AppDataBlob],
+    ) -> Result<(ArmTransaction, AppResource), String>;
+
+    fn transform(
+        &self,
+        consumed: &AppResource,
+        merkle_path: MerklePath<32>,
+        params: &serde_json::Value,
+        app_data: &[AppDataBlob],
+    ) -> Result<(ArmTransaction, Resource), String>;
+
+    // Returns the ephemeral resource that balances the action.
+    fn consume(
+        &self,
+        consumed: &AppResource,
+        merkle_path: MerklePath<32>,
+    ) -> Result<(ArmTransaction, Resource), String>;
+
+    // Apps whose resources cannot change hands keep the default.
+    fn transfer(
+        &self,
+        _consumed: &AppResource,
+        _merkle_path: MerklePath<32>,
+        _recipient: &NullifierKeyCommitment,
+    ) -> Result<(ArmTransaction, Resource), String> {
+        Err(format!("{} resources cannot be transferred", self.name()))
+    }
+
+    fn expected_image_ids(&self) -> ExpectedImageIds {
+        ExpectedImageIds::for_logic(vec![self.logic_ref()])
+    }
+}
+
+pub struct Registry {
+    apps: BTreeMap<&'static str, Arc<dyn Application>>,
+}
+
+impl Registry {
+    pub fn with_defaults() -> Self {
+        let mut registry = Registry { apps: BTreeMap::new() };
+        registry.register(Arc::new(CounterApp));
+        registry
+    }
+
+    pub fn register(&mut self, app: Arc<dyn Application>) {
+        self.apps.insert(app.name(), app);
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn Application>, String> {
+        self.apps
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown application: {}", name))
+    }
+
+    pub fn list(&self) -> Vec<Arc<dyn Application>> {
+        self.apps.values().cloned().collect()
+    }
+}
+
+// What to do with an app's resources. Params are stored as JSON text so that the
+// operation can be bincode-encoded into an artifact.
+#[derive(Serialize, Deserialize, Clone)]
+pub enum AppOperation {
+    Create { params: String, app_data: Vec<AppDataItem>, forwarder_call: Option<ForwarderCall> },
+    Transform {
+        consumed: AppResource,
+        params: String,
+        app_data: Vec<AppDataItem>,
+        forwarder_call: Option<ForwarderCall>,
+    },
+    Consume { consumed: AppResource },
+    // `recipient` is the recipient's nullifier key commitment (cnk)
+    Transfer { consumed: AppResource, recipient: [u8; 32] },
+}
+
+impl AppOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppOperation::Create { .. } => "create",
+            AppOperation::Transform { .. } => "transform",
+            AppOperation::Consume { .. } => "consume",
+            AppOperation::Transfer { .. } => "transfer",
+        }
+    }
+
+    pub fn consumed(&self) -> Option<&AppResource> {
+        match self {
+            AppOperation::Create { .. } => None,
+            AppOperation::Transform { consumed, .. }
+            | AppOperation::Consume { consumed }
+            | AppOperation::Transfer { consumed, .. } => Some(consumed),
+        }
+    }
+}
+
+fn parse_params(params: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(params).map_err(|e| format!("Invalid params: {}", e))
+}
+
+pub struct ProvedOperation {
+    pub tx: ArmTransaction,
+    // Resource created for the caller, with the key to consume it. For a consume,
+    // the ephemeral resource that balanced it.
+    pub created: Option<AppResource>,
+    // Resource created for another user by a transfer
+    pub sent: Option<Resource>,
+}
+
+// Builds the transaction of an operation. `merkle_path` is required when the
+// operation consumes a resource.
+pub fn prove(
+    app: &dyn Application,
+    operation: &AppOperation,
+    merkle_path: Option<MerklePath<32>>,
+) -> Result<ProvedOperation, String> {
+    let merkle_path = || merkle_path.ok_or_else(|| "Missing Merkle path for the consumed resource".to_string());
+    let proved = |tx, created, sent| ProvedOperation { tx, created, sent };
+    // A forwarder call comes first, where the Protocol Adapter looks for it
+    let app_data = |items: &[AppDataItem], call: &Option<ForwarderCall>| {
+        if (!items.is_empty() || call.is_some()) && !app.commits_app_data() {
+            return Err(format!("{} logic does not commit app data", app.name()));
+        }
+        let mut blobs: Vec<AppDataBlob> = call.iter().map(ForwarderCall::to_blob).collect();
+        blobs.extend(app_data::encode_all(items)?);
+        Ok(blobs)
+    };
+    match operation {
+        AppOperation::Create {
+            params,
+            app_data: items,
+            forwarder_call,
+        } => {
+            let blobs = app_data(items, forwarder_call)?;
+            let (tx, created) = app.create(&parse_params(params)?, &blobs)?;
+            app_data::check_committed(&tx, &created.resource, &blobs)?;
+            Ok(proved(tx, Some(created), None))
+        }
+        AppOperation::Transform {
+            consumed,
+            params,
+            app_data: items,
+            forwarder_call,
+        } => {
+            let blobs = app_data(items, forwarder_call)?;
+            let (tx, resource) = app.transform(consumed, merkle_path()?, &parse_params(params)?, &blobs)?;
+            app_data::check_committed(&tx, &resource, &blobs)?;
+            let created = AppResource {
+                resource,
+                nf_key: consumed.nf_key.clone(),
+            };
+            Ok(proved(tx, Some(created), None))
+        }
+        AppOperation::Consume { consumed } => {
+            let (tx, ephemeral) = app.consume(consumed, merkle_path()?)?;
+            let created = AppResource {
+                resource: ephemeral,
+                nf_key: consumed.nf_key.clone(),
+            };
+            Ok(proved(tx, Some(created), None))
+        }
+        AppOperation::Transfer { consumed, recipient } => {
+            let recipient = NullifierKeyCommitment::from_bytes(recipient);
+            let (tx, sent) = app.transfer(consumed, merkle_path()?, &recipient)?;
+            Ok(proved(tx, None, Some(sent)))
+        }
+    }
+}
+
+pub struct AppReceipt {
+    pub tx_hash: String,
+    pub artifact_hash: String,
+    pub evm_tx: ProtocolAdapter::Transaction,
+    pub created: Option<AppResource>,
+    pub sent: Option<Resource>,
+}
+
+// Proves, verifies, stores and submits an operation of a registered app.
+pub async fn run(
+    app: Arc<dyn Application>,
+    artifacts: Arc<ArtifactStore>,
+    user_account: &str,
+    operation: AppOperation,
+) -> Result<AppReceipt, String> {
+    let adapter = protocol_adapter();
+
+    // Step 1: Get the Merkle path of the consumed resource and the verifier selector
+    let merkle_path = match operation.consumed() {
+        Some(consumed) => {
+            let commitment = B256::from_slice(consumed.resource.commitment().as_bytes());
+            Some(crate::get_merkle_path(&adapter, commitment).await?)
+        }
+        None => None,
+    };
+    let verifier_selector = verification::get_verifier_selector(&adapter).await?;
+
+    // Step 2: Prove the operation, verify it locally and store the artifact
+    let store = artifacts.clone();
+    let artifact_account = user_account.to_string();
+    let (evm_tx, artifact_hash, created, sent) = tokio::task::spawn_blocking(move || {
+        let ProvedOperation { tx, created, sent } = prove(app.as_ref(), &operation, merkle_path)?;
+        let evm_tx = ProtocolAdapter::Transaction::from(tx.clone());
+        verification::verify_transaction(&tx, &evm_tx, &app.expected_image_ids(), Some(verifier_selector))?;
+
+        let inputs = ProvingInputs::App {
+            app: app.name().to_string(),
+            operation,
+            created: created.clone(),
+            sent: sent.clone(),
+        };
+        let artifact_hash = store.save(&inputs, Some(&artifact_account), &tx, &evm_tx)?;
+        Ok::<_, String>((evm_tx, artifact_hash, created, sent))
+    })
+    .await
+    .map_err(|e| format!("Failed to prove app operation: {}", e))??;
+
+    // Step 3: Submit to Protocol Adapter and wait for the receipt. Callers advance
+    // their stores only on success, so a reverted transaction must be an error here.
+    match execute_confirmed(evm_tx.clone()).await {
+        Ok(tx_hash) => {
+            artifacts.set_status(&artifact_hash, ArtifactStatus::Submitted { tx_hash: tx_hash.clone() })?;
+            Ok(AppReceipt {
+                tx_hash,
+                artifact_hash,
+                evm_tx,
+                created,
+                sent,
+            })
+        }
+        Err(e) => {
+            artifacts.set_status(&artifact_hash, ArtifactStatus::Failed { error: e.clone() })?;
+            Err(format!(
+                "Failed to submit transaction: {}. Proved transaction stored as artifact {}",
+                e, artifact_hash
+            ))
+        }
+    }
+}
+
+// Operations that consume a resource sign its commitment as a `Resource:` line, so
+// the signature cannot be replayed against another resource.
+pub fn verify_resource_line(message: &str, commitment: &str) -> Result<(), String> {
+    let resource_line = format!("Resource: {}", commitment);
+    if !message.lines().any(|line| line.trim().eq_ignore_ascii_case(&resource_line)) {
+        return Err("Message does not contain the expected resource".to_string());
+    }
+    Ok(())
+}
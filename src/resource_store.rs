@@ -0,0 +1,83 @@
+// In-memory store of resources created through the generic /apps endpoints, keyed
+// by commitment, with the nullifier keys needed to consume them. Counters are kept
+// in the counter store instead.
+
+use std::collections::HashMap;
+
+use alloy::primitives::hex;
+use arm_risc0::resource::Resource;
+
+use crate::artifacts::AppUpdate;
+use crate::registry::AppResource;
+
+#[derive(Clone)]
+pub struct StoredResource {
+    pub app: String,
+    pub owner: String,
+    pub resource: AppResource,
+    pub created_in: Option<String>,
+    // Set once the resource has been consumed by one of our transactions
+    pub consumed_in: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ResourceStore {
+    resources: HashMap<String, StoredResource>,
+}
+
+pub fn commitment_hex(resource: &Resource) -> String {
+    format!("0x{}", hex::encode(resource.commitment().as_bytes()))
+}
+
+impl ResourceStore {
+    pub fn insert(&mut self, app: &str, owner: &str, resource: AppResource, created_in: Option<String>) -> String {
+        let commitment = commitment_hex(&resource.resource);
+        self.resources.insert(
+            commitment.clone(),
+            StoredResource {
+                app: app.to_string(),
+                owner: owner.to_string(),
+                resource,
+                created_in,
+                consumed_in: None,
+            },
+        );
+        commitment
+    }
+
+    // An unconsumed resource of `app` owned by `owner`.
+    pub fn spendable(&self, app: &str, owner: &str, commitment: &str) -> Result<&StoredResource, String> {
+        let stored = self
+            .resources
+            .get(&commitment.to_lowercase())
+            .filter(|stored| stored.app == app && stored.owner == owner)
+            .ok_or_else(|| format!("No {} resource {} owned by {}", app, commitment, owner))?;
+        if let Some(tx_hash) = &stored.consumed_in {
+            return Err(format!("Resource {} was already consumed in {}", commitment, tx_hash));
+        }
+        Ok(stored)
+    }
+
+    pub fn for_account(&self, app: &str, owner: &str) -> Vec<(&String, &StoredResource)> {
+        let mut resources: Vec<_> = self
+            .resources
+            .iter()
+            .filter(|(_, stored)| stored.app == app && stored.owner == owner)
+            .collect();
+        resources.sort_by(|a, b| a.0.cmp(b.0));
+        resources
+    }
+
+    // Records the resources a submitted transaction consumed and created. The
+    // ephemeral resource balancing a consume cannot be spent and is not kept.
+    pub fn apply(&mut self, update: AppUpdate, tx_hash: &str) {
+        if let Some(consumed) = update.operation.consumed() {
+            if let Some(stored) = self.resources.get_mut(&commitment_hex(&consumed.resource)) {
+                stored.consumed_in = Some(tx_hash.to_string());
+            }
+        }
+        if let Some(created) = update.created.filter(|created| !created.resource.is_ephemeral) {
+            self.insert(&update.app, &update.user_account, created, Some(tx_hash.to_string()));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// Deterministic counter transactions from a seed.
+//
+// The ARM and counter-app helpers draw nullifier keys, nonces, resource randomness
+// and rcv from the OS RNG, so two runs never produce the same transaction. This
+// module builds the same resources and compliance witnesses itself and draws every
+// random value from a ChaCha20 stream seeded by the caller. Resources, nonces and
+// rcv values are then bit-identical across runs. Transactions are too in dev mode;
+// real proofs are randomized by the prover.
+//
+// The golden file `fixtures/seeded_counter.json` records the init-then-increment
+// flow for a fixed seed, and a unit test compares a fresh run against it byte for
+// byte. `SEEDED_REGENERATE=1 cargo test seeded::` rewrites it.
+
+use arm_risc0::action::Action;
+use arm_risc0::compliance::ComplianceWitness;
+use arm_risc0::compliance_unit::ComplianceUnit;
+use arm_risc0::delta_proof::DeltaWitness;
+use arm_risc0::merkle_path::MerklePath;
+use arm_risc0::nullifier_key::NullifierKey;
+use arm_risc0::resource::Resource;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use risc0_zkvm::sha::Digest;
+
+use crate::counter::{self, CounterTransition};
+
+pub const GOLDEN_SEED: u64 = 42;
+
+pub struct SeededRng(ChaCha20Rng);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        SeededRng(ChaCha20Rng::seed_from_u64(seed))
+    }
+
+    pub fn bytes(&mut self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.0.fill_bytes(&mut bytes);
+        bytes
+    }
+
+    pub fn digest(&mut self) -> Digest {
+        Digest::from_bytes(self.bytes())
+    }
+
+    pub fn nullifier_key(&mut self) -> NullifierKey {
+        NullifierKey::from_bytes(&self.bytes())
+    }
+
+    // A uniformly random non-zero scalar, as the delta witness requires
+    pub fn rcv(&mut self) -> Vec<u8> {
+        k256::NonZeroScalar::random(&mut self.0).to_bytes().to_vec()
+    }
+
+    // Replaces the randomness `reset_randomness` drew from the OS RNG.
+    pub fn reset_randomness(&mut self, resource: &mut Resource) {
+        resource.rand_seed = self.bytes();
+    }
+}
+
+// Proves one counter action like `app::generate_compliance_proof`, but with a
+// seeded rcv. Returns the rcv along with the action.
+fn prove_action(
+    rng: &mut SeededRng,
+    consumed: Resource,
+    nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+    created: Resource,
+) -> (Action, Vec<u8>) {
+    let mut witness =
+        ComplianceWitness::from_resources_with_path(consumed.clone(), nf_key.clone(), merkle_path, created.clone());
+    witness.rcv = rng.rcv();
+    let compliance_unit = ComplianceUnit::create(&witness);
+    let logic_verifier_inputs = app::generate_logic_proofs(consumed, nf_key, created);
+    (Action::new(vec![compliance_unit], logic_verifier_inputs), witness.rcv)
+}
+
+fn into_transaction(action: Action, rcv: &[u8]) -> ArmTransaction {
+    let mut tx = ArmTransaction::create(vec![action], Delta::Witness(DeltaWitness::from_bytes(rcv)));
+    tx.generate_delta_proof();
+    tx
+}
+
+// Seeded `app::init::create_init_counter_tx`: consumes an ephemeral counter at 0 and
+// creates the counter. Returns the transaction, the counter, its key and the rcv.
+pub fn create_init_counter_tx(rng: &mut SeededRng) -> (ArmTransaction, Resource, NullifierKey, Vec<u8>) {
+    let nf_key = rng.nullifier_key();
+    let mut ephemeral = Resource::create(
+        *app::COUNTER_ID,
+        rng.digest(),
+        1,
+        Digest::default(),
+        true,
+        rng.digest(),
+        nf_key.commit(),
+    );
+    ephemeral.set_value_ref(app::convert_counter_to_value_ref(0));
+    rng.reset_randomness(&mut ephemeral);
+
+    let nullifier = ephemeral.nullifier(&nf_key).expect("the key is the ephemeral counter's");
+    let mut created = ephemeral.clone();
+    created.is_ephemeral = false;
+    created.set_nonce(nullifier);
+    rng.reset_randomness(&mut created);
+
+    let (action, rcv) = prove_action(rng, ephemeral, nf_key.clone(), MerklePath::default(), created.clone());
+    (into_transaction(action, &rcv), created, nf_key, rcv)
+}
+
+// Seeded `counter::create_transition_tx_with_merkle_path`. Returns the transaction,
+// the new counter and the rcv.
+pub fn create_transition_tx(
+    rng: &mut SeededRng,
+    counter_resource: Resource,
+    nf_key: NullifierKey,
+    merkle_path: MerklePath<32>,
+    transition: CounterTransition,
+) -> Result<(ArmTransaction, Resource, Vec<u8>), String> {
+    let mut created = counter::transition_counter(&counter_resource, &nf_key, transition)?;
+    rng.reset_randomness(&mut created);
+    let (action, rcv) = prove_action(rng, counter_resource, nf_key, merkle_path, created.clone());
+    Ok((into_transaction(action, &rcv), created, rcv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{hex, keccak256};
+    use serde::{Deserialize, Serialize};
+
+    use crate::verification::{self, ExpectedImageIds};
+
+    const GOLDEN_PATH: &str = "fixtures/seeded_counter.json";
+
+    // What one step of the seeded flow produced, hex-encoded.
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct GoldenStep {
+        resource: String,    // bincode of the created resource
+        commitment: String,
+        rcv: String,
+        transaction: String, // keccak256 of the bincode-encoded ARM transaction
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    struct GoldenFlow {
+        seed: u64,
+        init: GoldenStep,
+        increment: GoldenStep,
+    }
+
+    fn golden_step(tx: &ArmTransaction, created: &Resource, rcv: &[u8]) -> Result<GoldenStep, String> {
+        let resource = bincode::serialize(created).map_err(|e| format!("Failed to encode resource: {}", e))?;
+        let tx_bytes = bincode::serialize(tx).map_err(|e| format!("Failed to encode transaction: {}", e))?;
+        Ok(GoldenStep {
+            resource: hex::encode(resource),
+            commitment: hex::encode(created.commitment().as_bytes()),
+            rcv: hex::encode(rcv),
+            transaction: format!("0x{}", hex::encode(keccak256(&tx_bytes))),
+        })
+    }
+
+    // Initializes a counter and increments it, verifying both transactions.
+    fn run_flow(seed: u64) -> Result<GoldenFlow, String> {
+        let mut rng = SeededRng::new(seed);
+        let (init_tx, counter, nf_key, init_rcv) = create_init_counter_tx(&mut rng);
+        verification::verify_arm_proofs(&init_tx, &ExpectedImageIds::counter()).into_result()?;
+        let init = golden_step(&init_tx, &counter, &init_rcv)?;
+
+        let (increment_tx, next, increment_rcv) =
+            create_transition_tx(&mut rng, counter, nf_key, MerklePath::default(), CounterTransition::Increment)?;
+        verification::verify_arm_proofs(&increment_tx, &ExpectedImageIds::counter()).into_result()?;
+        if counter::counter_value(&next) != 1 {
+            return Err(format!("Increment produced value {}", counter::counter_value(&next)));
+        }
+        let increment = golden_step(&increment_tx, &next, &increment_rcv)?;
+
+        Ok(GoldenFlow { seed, init, increment })
+    }
+
+    fn compare_step(name: &str, expected: &GoldenStep, actual: &GoldenStep) -> Vec<String> {
+        [
+            ("resource", &expected.resource, &actual.resource),
+            ("commitment", &expected.commitment, &actual.commitment),
+            ("rcv", &expected.rcv, &actual.rcv),
+            ("transaction", &expected.transaction, &actual.transaction),
+        ]
+        .into_iter()
+        .filter(|(_, expected, actual)| expected != actual)
+        .map(|(field, expected, actual)| format!("{}.{}: expected {}, got {}", name, field, expected, actual))
+        .collect()
+    }
+
+    // Regenerate the golden file only for an intended change, such as an ARM
+    // upgrade, and review its diff.
+    #[test]
+    fn flow_matches_the_golden_file() {
+        verification::use_dev_mode();
+        let flow = run_flow(GOLDEN_SEED).unwrap();
+        let json = serde_json::to_string_pretty(&flow).unwrap() + "\n";
+        if std::env::var("SEEDED_REGENERATE").as_deref() == Ok("1") {
+            std::fs::write(GOLDEN_PATH, &json).unwrap();
+            return;
+        }
+
+        let contents = std::fs::read_to_string(GOLDEN_PATH).unwrap_or_else(|e| {
+            panic!("Failed to read {} (create it with SEEDED_REGENERATE=1 cargo test seeded::): {}", GOLDEN_PATH, e)
+        });
+        if contents != json {
+            let golden: GoldenFlow = serde_json::from_str(&contents).unwrap();
+            let mut differences = compare_step("init", &golden.init, &flow.init);
+            differences.extend(compare_step("increment", &golden.increment, &flow.increment));
+            panic!("{} differs from the seeded flow:\n{}", GOLDEN_PATH, differences.join("\n"));
+        }
+    }
+
+    #[test]
+    fn same_seed_reproduces_the_flow() {
+        verification::use_dev_mode();
+        let flow = run_flow(GOLDEN_SEED).unwrap();
+        assert_eq!(run_flow(GOLDEN_SEED).unwrap(), flow);
+        assert_ne!(run_flow(GOLDEN_SEED + 1).unwrap().init.commitment, flow.init.commitment);
+    }
+
+    #[test]
+    fn seeded_rng_is_deterministic() {
+        let (mut first, mut second) = (SeededRng::new(GOLDEN_SEED), SeededRng::new(GOLDEN_SEED));
+        assert_eq!(first.bytes(), second.bytes());
+        assert_eq!(first.rcv(), second.rcv());
+        assert_ne!(SeededRng::new(GOLDEN_SEED + 1).bytes(), SeededRng::new(GOLDEN_SEED).bytes());
+    }
+}
@@ -0,0 +1,134 @@
+// Shapes and cost reports of the test transactions the stress endpoint emits.
+//
+// Test transactions are proved with the ARM's test logic. Each of its actions holds
+// one compliance unit, so two resources (one consumed, one created), and its logic
+// instances commit an empty ciphertext and no app data. The only dimension a shape
+// varies is therefore the number of actions; the report measures what the
+// transaction actually carries.
+//
+// Shapes with more compliance units per action, or with app data and ciphertext
+// blobs of a chosen size, are out of scope: they need a logic whose proofs cover
+// every unit of an action and commit the blobs, and neither the test logic nor the
+// counter-app guest of arm-risc0 v0.3.0 does either.
+
+use std::time::Duration;
+
+use alloy::sol_types::SolCall;
+use arm_risc0::transaction::{self, Transaction as ArmTransaction};
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_ACTIONS: usize = 8;
+
+// Gas limit per action of a submitted test transaction
+pub const GAS_PER_ACTION: u64 = 3_000_000;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TransactionShape {
+    pub actions: usize,
+}
+
+impl Default for TransactionShape {
+    fn default() -> Self {
+        TransactionShape { actions: 1 }
+    }
+}
+
+impl TransactionShape {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.actions == 0 || self.actions > MAX_ACTIONS {
+            return Err(format!("actions must be between 1 and {}, got {}", MAX_ACTIONS, self.actions));
+        }
+        Ok(())
+    }
+
+    pub fn generate(&self) -> Result<ArmTransaction, String> {
+        self.validate()?;
+        Ok(transaction::generate_test_transaction(self.actions))
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        GAS_PER_ACTION * self.actions as u64
+    }
+}
+
+// Sizes are in bytes, as submitted to the Protocol Adapter.
+#[derive(Serialize, Clone, Debug)]
+pub struct TransactionReport {
+    pub actions: usize,
+    pub compliance_units: usize,
+    pub logic_proofs: usize,
+    pub compliance_proof_bytes: usize,
+    pub logic_proof_bytes: usize,
+    pub delta_proof_bytes: usize,
+    pub ciphertext_bytes: usize,
+    pub app_data_bytes: usize,
+    pub calldata_bytes: usize,
+    pub proving_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<u64>,
+}
+
+impl TransactionReport {
+    pub fn measure(evm_tx: &ProtocolAdapter::Transaction, proving_time: Duration) -> Self {
+        let units = || evm_tx.actions.iter().flat_map(|action| action.complianceVerifierInputs.iter());
+        let logic_inputs = || evm_tx.actions.iter().flat_map(|action| action.logicVerifierInputs.iter());
+        let calldata = ProtocolAdapter::executeCall {
+            transaction: evm_tx.clone(),
+        }
+        .abi_encode();
+
+        TransactionReport {
+            actions: evm_tx.actions.len(),
+            compliance_units: units().count(),
+            logic_proofs: logic_inputs().count(),
+            compliance_proof_bytes: units().map(|unit| unit.proof.len()).sum(),
+            logic_proof_bytes: logic_inputs().map(|input| input.proof.len()).sum(),
+            delta_proof_bytes: evm_tx.deltaProof.len(),
+            ciphertext_bytes: logic_inputs().map(|input| input.instance.ciphertext.len()).sum(),
+            app_data_bytes: logic_inputs()
+                .flat_map(|input| input.instance.appData.iter())
+                .map(|blob| blob.blob.len())
+                .sum(),
+            calldata_bytes: calldata.len(),
+            proving_ms: proving_time.as_millis(),
+            gas_used: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification;
+
+    // Resources in one test action: the consumed and the created resource of its unit
+    const TEST_RESOURCES_PER_ACTION: usize = 2;
+
+    #[test]
+    fn action_count_is_bounded() {
+        assert!(TransactionShape::default().validate().is_ok());
+        assert!(TransactionShape { actions: MAX_ACTIONS }.validate().is_ok());
+        assert!(TransactionShape { actions: 0 }.validate().is_err());
+        assert!(TransactionShape { actions: MAX_ACTIONS + 1 }.generate().is_err());
+        assert_eq!(TransactionShape { actions: 3 }.gas_limit(), 3 * GAS_PER_ACTION);
+    }
+
+    #[test]
+    fn report_counts_what_the_test_transaction_carries() {
+        verification::use_dev_mode();
+        let shape = TransactionShape { actions: 2 };
+        let evm_tx = ProtocolAdapter::Transaction::from(shape.generate().unwrap());
+        let report = TransactionReport::measure(&evm_tx, Duration::from_millis(1500));
+
+        assert_eq!(report.actions, 2);
+        assert_eq!(report.compliance_units, 2);
+        assert_eq!(report.logic_proofs, 2 * TEST_RESOURCES_PER_ACTION);
+        assert_eq!(report.ciphertext_bytes, 0);
+        assert_eq!(report.app_data_bytes, 0);
+        assert_eq!(report.proving_ms, 1500);
+        assert!(report.calldata_bytes > report.compliance_proof_bytes + report.logic_proof_bytes);
+        assert_eq!(report.gas_used, None);
+    }
+}
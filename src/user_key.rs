@@ -0,0 +1,141 @@
+// UserKeys shared between users, as produced by `serializeUserKey` in
+// frontend/src/services/cryptography.ts: base64 of five fields, each prefixed with
+// its length as a little-endian u32, in the order idpk, cnk, sepk, sdpk, signature.
+//
+// The signature is the identity key's secp256k1 signature over
+// sha256(cnk || sdpk || sepk).
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use k256::ecdsa::{Signature, VerifyingKey};
+use sha2::{Digest as _, Sha256};
+
+#[derive(Clone, Debug)]
+pub struct UserKey {
+    pub idpk: Vec<u8>, // identity public key, 33 bytes compressed
+    pub cnk: [u8; 32], // nullifier key commitment
+    pub sepk: Vec<u8>, // static encryption public key, 33 bytes compressed
+    pub sdpk: Vec<u8>, // static discovery public key, 33 bytes compressed
+    pub signature: Vec<u8>,
+}
+
+impl UserKey {
+    pub fn parse(serialized: &str) -> Result<Self, String> {
+        let bytes = BASE64
+            .decode(serialized.trim())
+            .map_err(|e| format!("UserKey is not valid base64: {}", e))?;
+
+        let mut offset = 0;
+        let mut read_field = |name: &str| -> Result<Vec<u8>, String> {
+            let len_bytes: [u8; 4] = bytes
+                .get(offset..offset + 4)
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| format!("UserKey is truncated before {}", name))?;
+            let len = u32::from_le_bytes(len_bytes) as usize;
+            offset += 4;
+            let field = bytes
+                .get(offset..offset + len)
+                .ok_or_else(|| format!("UserKey is truncated in {}", name))?
+                .to_vec();
+            offset += len;
+            Ok(field)
+        };
+
+        let idpk = read_field("idpk")?;
+        let cnk = read_field("cnk")?;
+        let sepk = read_field("sepk")?;
+        let sdpk = read_field("sdpk")?;
+        let signature = read_field("signature")?;
+
+        Ok(UserKey {
+            idpk,
+            cnk: cnk
+                .try_into()
+                .map_err(|cnk: Vec<u8>| format!("UserKey cnk must be 32 bytes, got {}", cnk.len()))?,
+            sepk,
+            sdpk,
+            signature,
+        })
+    }
+
+    // Checks the identity signature binding cnk, sdpk and sepk to idpk.
+    pub fn verify(&self) -> Result<(), String> {
+        let verifying_key =
+            VerifyingKey::from_sec1_bytes(&self.idpk).map_err(|e| format!("Invalid UserKey idpk: {}", e))?;
+        let signature =
+            Signature::from_slice(&self.signature).map_err(|e| format!("Invalid UserKey signature: {}", e))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.cnk);
+        hasher.update(&self.sdpk);
+        hasher.update(&self.sepk);
+        let message_hash = hasher.finalize();
+
+        verifying_key
+            .verify_prehash(&message_hash, &signature)
+            .map_err(|_| "UserKey signature does not verify".to_string())
+    }
+
+    pub fn parse_verified(serialized: &str) -> Result<Self, String> {
+        let user_key = Self::parse(serialized)?;
+        user_key.verify()?;
+        Ok(user_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+
+    fn public_key() -> Vec<u8> {
+        SigningKey::random(&mut rand::thread_rng()).verifying_key().to_sec1_bytes().to_vec()
+    }
+
+    // Signs and serializes a UserKey the way `serializeUserKey` does
+    fn serialized(identity: &SigningKey, cnk: [u8; 32], sepk: &[u8], sdpk: &[u8]) -> String {
+        let message_hash = Sha256::new().chain_update(cnk).chain_update(sdpk).chain_update(sepk).finalize();
+        let signature: Signature = identity.sign_prehash(&message_hash).unwrap();
+
+        let idpk = identity.verifying_key().to_sec1_bytes();
+        let mut bytes = vec![];
+        for field in [&idpk[..], &cnk, sepk, sdpk, &signature.to_bytes()] {
+            bytes.extend((field.len() as u32).to_le_bytes());
+            bytes.extend(field);
+        }
+        BASE64.encode(bytes)
+    }
+
+    #[test]
+    fn parses_and_verifies_a_signed_user_key() {
+        let identity = SigningKey::random(&mut rand::thread_rng());
+        let (sepk, sdpk) = (public_key(), public_key());
+        let user_key = UserKey::parse_verified(&serialized(&identity, [7; 32], &sepk, &sdpk)).unwrap();
+
+        assert_eq!(user_key.cnk, [7; 32]);
+        assert_eq!(user_key.sepk, sepk);
+        assert_eq!(user_key.sdpk, sdpk);
+    }
+
+    #[test]
+    fn swapped_keys_do_not_verify() {
+        let identity = SigningKey::random(&mut rand::thread_rng());
+        let (sepk, sdpk) = (public_key(), public_key());
+        let mut user_key = UserKey::parse(&serialized(&identity, [7; 32], &sepk, &sdpk)).unwrap();
+
+        user_key.sdpk = public_key();
+        assert!(user_key.verify().is_err());
+    }
+
+    #[test]
+    fn truncated_user_key_is_rejected() {
+        let identity = SigningKey::random(&mut rand::thread_rng());
+        let bytes = BASE64.decode(serialized(&identity, [7; 32], &public_key(), &public_key())).unwrap();
+
+        let error = UserKey::parse(&BASE64.encode(&bytes[..bytes.len() - 1])).unwrap_err();
+        assert!(error.contains("truncated in signature"), "{}", error);
+        assert!(UserKey::parse("not base64!").is_err());
+    }
+}
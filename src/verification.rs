@@ -0,0 +1,306 @@
+// Local verification of ARM transactions before they are broadcast.
+//
+// Proving is expensive and a rejected `execute` call still costs gas, so every
+// transaction is checked here first: the compliance, logic and delta proofs of the
+// ARM `Transaction` are verified locally, the verifying keys are pinned to the
+// expected image IDs, and the converted EVM struct is re-checked against the ARM
+// transaction (the conversion has corrupted proofs in the past).
+
+use std::sync::OnceLock;
+
+use alloy::primitives::{hex, B256};
+use arm_risc0::action::Action;
+use arm_risc0::transaction::{Delta, Transaction as ArmTransaction};
+use evm_protocol_adapter_bindings::conversion::ProtocolAdapter;
+use risc0_zkvm::sha::Digest;
+
+use crate::conversion;
+
+// Image IDs the proofs of a transaction are expected to be produced by.
+#[derive(Clone)]
+pub struct ExpectedImageIds {
+    pub compliance: Digest,
+    // `None` skips pinning logic verifying keys (e.g. for the ARM test transaction,
+    // whose resources use the test logic of the ARM crate).
+    pub logic: Option<Vec<Digest>>,
+}
+
+impl ExpectedImageIds {
+    // Compliance circuit plus the given application logics.
+    pub fn for_logic(logic: Vec<Digest>) -> Self {
+        ExpectedImageIds {
+            compliance: expected_compliance_id(),
+            logic: Some(logic),
+        }
+    }
+
+    // Compliance circuit plus the counter application logic.
+    pub fn counter() -> Self {
+        Self::for_logic(vec![*app::COUNTER_ID])
+    }
+
+    // Compliance circuit only; logic verifying keys are not pinned.
+    pub fn any_logic() -> Self {
+        ExpectedImageIds {
+            compliance: expected_compliance_id(),
+            logic: None,
+        }
+    }
+}
+
+static EXPECTED_COMPLIANCE_ID: OnceLock<Digest> = OnceLock::new();
+
+// The compliance image ID the deployed verifier expects. Defaults to the one the
+// ARM crate was built with; EXPECTED_COMPLIANCE_IMAGE_ID pins it explicitly. Loaded
+// once at startup, so an invalid value stops the backend instead of failing requests.
+pub fn load_expected_compliance_id() -> Result<Digest, String> {
+    let id = match std::env::var("EXPECTED_COMPLIANCE_IMAGE_ID") {
+        Ok(id) => Digest::from_hex(id.trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid EXPECTED_COMPLIANCE_IMAGE_ID {}: {}", id, e))?,
+        Err(_) => *arm_risc0::constants::COMPLIANCE_VK,
+    };
+    Ok(*EXPECTED_COMPLIANCE_ID.get_or_init(|| id))
+}
+
+fn expected_compliance_id() -> Digest {
+    EXPECTED_COMPLIANCE_ID
+        .get()
+        .copied()
+        .unwrap_or(*arm_risc0::constants::COMPLIANCE_VK)
+}
+
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    pub errors: Vec<String>,
+}
+
+impl VerificationReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn into_result(self) -> Result<(), String> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(self.errors.join("; "))
+        }
+    }
+
+    fn fail(&mut self, msg: String) {
+        println!("Verification failed: {}", msg);
+        self.errors.push(msg);
+    }
+}
+
+pub fn digest_to_b256(digest: &Digest) -> B256 {
+    B256::from_slice(digest.as_bytes())
+}
+
+// Dev mode receipts are fake and carry no real seal, so selector checks are skipped.
+pub fn is_dev_mode() -> bool {
+    matches!(
+        std::env::var("RISC0_DEV_MODE").as_deref(),
+        Ok("1") | Ok("true")
+    )
+}
+
+// Tests prove with fake receipts, like `RISC0_DEV_MODE=1 cargo run`.
+#[cfg(test)]
+pub fn use_dev_mode() {
+    static DEV_MODE: std::sync::Once = std::sync::Once::new();
+    // SAFETY: the variable is only read through `std::env`, which serializes access,
+    // and every test sets the same value before proving.
+    DEV_MODE.call_once(|| unsafe { std::env::set_var("RISC0_DEV_MODE", "1") });
+}
+
+// Verifies the compliance, logic and delta proofs of an ARM transaction locally.
+pub fn verify_arm_proofs(tx: &ArmTransaction, expected: &ExpectedImageIds) -> VerificationReport {
+    let mut report = verify_actions(tx, expected);
+
+    match &tx.delta_proof {
+        Delta::Proof(_) => {
+            if !tx.verify() {
+                report.fail("delta proof does not verify".to_string());
+            }
+        }
+        Delta::Witness(_) => {
+            report.fail("delta proof has not been generated".to_string());
+        }
+    }
+
+    report
+}
+
+// Verifies the compliance and logic proofs of every action, without the delta proof.
+// Partial transactions, which are not balanced yet, can only be checked this far.
+pub fn verify_actions(tx: &ArmTransaction, expected: &ExpectedImageIds) -> VerificationReport {
+    let mut report = VerificationReport::default();
+    if let Err(e) = check_compliance_key(expected) {
+        report.fail(e);
+    }
+    for (i, action) in tx.actions.iter().enumerate() {
+        for error in verify_action(action, expected) {
+            report.fail(format!("action {} {}", i, error));
+        }
+    }
+    report
+}
+
+// `ComplianceUnit::verify` checks against the compliance key the ARM crate was built
+// with, so that key has to be the one we expect.
+pub fn check_compliance_key(expected: &ExpectedImageIds) -> Result<(), String> {
+    if expected.compliance != *arm_risc0::constants::COMPLIANCE_VK {
+        return Err(format!(
+            "compliance image ID mismatch: expected 0x{}, ARM crate uses 0x{}",
+            hex::encode(expected.compliance.as_bytes()),
+            hex::encode(arm_risc0::constants::COMPLIANCE_VK.as_bytes())
+        ));
+    }
+    Ok(())
+}
+
+// Verifies the proofs of a single action. Errors name the compliance unit or logic
+// input they concern.
+pub fn verify_action(action: &Action, expected: &ExpectedImageIds) -> Vec<String> {
+    let mut errors = vec![];
+    for (j, unit) in action.compliance_units.iter().enumerate() {
+        if !unit.verify() {
+            errors.push(format!("compliance unit {}: proof does not verify", j));
+        }
+    }
+
+    for (j, input) in action.logic_verifier_inputs.iter().enumerate() {
+        if let Some(logic_ids) = &expected.logic {
+            if !logic_ids.contains(&input.verifying_key) {
+                errors.push(format!(
+                    "logic input {}: unexpected verifying key 0x{}",
+                    j,
+                    hex::encode(input.verifying_key.as_bytes())
+                ));
+            }
+        }
+    }
+
+    // Action verification checks every logic proof against the action tree built
+    // from the compliance units' tags.
+    if !action.verify() {
+        errors.push("logic proofs do not verify".to_string());
+    }
+    errors
+}
+
+// Re-checks the converted EVM struct field by field against the ARM transaction.
+pub fn verify_evm_conversion(
+    tx: &ArmTransaction,
+    evm_tx: &ProtocolAdapter::Transaction,
+    verifier_selector: Option<[u8; 4]>,
+) -> VerificationReport {
+    let mut report = VerificationReport::default();
+
+    for mismatch in conversion::diff_conversion(tx, evm_tx) {
+        report.fail(format!("conversion mismatch at {}", mismatch));
+    }
+
+    // Every EVM proof must be routed to the verifier the Protocol Adapter uses.
+    if let Some(selector) = verifier_selector {
+        for (i, evm_action) in evm_tx.actions.iter().enumerate() {
+            let proofs = evm_action
+                .complianceVerifierInputs
+                .iter()
+                .map(|input| ("compliance", &input.proof))
+                .chain(evm_action.logicVerifierInputs.iter().map(|input| ("logic", &input.proof)));
+            for (kind, proof) in proofs {
+                if proof.len() < 4 || proof[0..4] != selector {
+                    report.fail(format!(
+                        "action {}: {} proof does not start with verifier selector 0x{}",
+                        i,
+                        kind,
+                        hex::encode(selector)
+                    ));
+                }
+            }
+        }
+    }
+
+    report
+}
+
+// Runs every local check; the caller refuses to submit when this fails.
+pub fn verify_transaction(
+    tx: &ArmTransaction,
+    evm_tx: &ProtocolAdapter::Transaction,
+    expected: &ExpectedImageIds,
+    verifier_selector: Option<[u8; 4]>,
+) -> Result<(), String> {
+    println!("Verifying transaction locally before broadcasting...");
+
+    let mut report = verify_arm_proofs(tx, expected);
+    let selector = if is_dev_mode() { None } else { verifier_selector };
+    report
+        .errors
+        .extend(verify_evm_conversion(tx, evm_tx, selector).errors);
+
+    if report.is_ok() {
+        println!("Local verification passed for {} actions", tx.actions.len());
+    }
+    report.into_result()
+}
+
+// Fetches the RISC Zero verifier selector the Protocol Adapter routes proofs to.
+pub async fn get_verifier_selector(
+    adapter: &ProtocolAdapter::ProtocolAdapterInstance<impl alloy::providers::Provider>,
+) -> Result<[u8; 4], String> {
+    let selector = adapter
+        .getRiscZeroVerifierSelector()
+        .call()
+        .await
+        .map_err(|e| format!("Failed to call getRiscZeroVerifierSelector: {}", e))?;
+    Ok(selector.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter_init() -> (ArmTransaction, ProtocolAdapter::Transaction) {
+        use_dev_mode();
+        let (tx, _, _) = app::init::create_init_counter_tx();
+        let evm_tx = ProtocolAdapter::Transaction::from(tx.clone());
+        (tx, evm_tx)
+    }
+
+    #[test]
+    fn dev_mode_transaction_verifies() {
+        let (tx, evm_tx) = counter_init();
+        assert!(verify_arm_proofs(&tx, &ExpectedImageIds::counter()).is_ok());
+        assert!(verify_evm_conversion(&tx, &evm_tx, None).is_ok());
+        assert_eq!(verify_transaction(&tx, &evm_tx, &ExpectedImageIds::counter(), None), Ok(()));
+    }
+
+    #[test]
+    fn unexpected_logic_key_is_reported() {
+        let (tx, _) = counter_init();
+        let report = verify_arm_proofs(&tx, &ExpectedImageIds::for_logic(vec![Digest::default()]));
+        assert!(report.errors.iter().any(|error| error.contains("unexpected verifying key")));
+    }
+
+    #[test]
+    fn mismatched_selector_is_reported() {
+        let (tx, evm_tx) = counter_init();
+        let report = verify_evm_conversion(&tx, &evm_tx, Some([0xde, 0xad, 0xbe, 0xef]));
+        assert!(!report.errors.is_empty());
+        assert!(report
+            .errors
+            .iter()
+            .all(|error| error.contains("does not start with verifier selector 0xdeadbeef")));
+    }
+
+    // Dev-mode receipts carry no seal, so the selector must not be checked there
+    #[test]
+    fn dev_mode_skips_the_selector_check() {
+        let (tx, evm_tx) = counter_init();
+        let selector = Some([0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(verify_transaction(&tx, &evm_tx, &ExpectedImageIds::counter(), selector), Ok(()));
+    }
+}